@@ -0,0 +1,15 @@
+use coreaudio_hl::{error::Error, events::stream};
+use futures::{executor::block_on, StreamExt};
+
+fn main() -> Result<(), Error> {
+    block_on(async {
+        let mut changes = stream::default_output_changes(16)?;
+        println!("Listening for default output changes...");
+
+        while let Some(device) = changes.next().await {
+            println!("New default output: {}", device?.name());
+        }
+
+        Ok(())
+    })
+}
@@ -5,12 +5,12 @@ use coreaudio_hl::{
 use std::{thread::sleep, time::Duration};
 
 fn main() {
-    register(my_callback).unwrap();
+    let subscription = register(Box::new(my_callback)).unwrap();
     println!("Callback registered!");
 
     sleep(Duration::from_secs(10));
 
-    unregister().unwrap();
+    unregister(subscription).unwrap();
     println!("Callback unregistered!");
 }
 
@@ -3,17 +3,20 @@ use coreaudio_hl::{
     devices::AudioDevice,
     mscope::PropertyScope,
     mselector::{AudioDevPropSelector, PropertySelector},
+    property::VOLUME_SCALAR,
 };
 use std::error::Error;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let device = AudioDevice::default_output()?;
-    let mut _address = AudioObjPropAddress::new(
-        PropertySelector::DEV_VOLUME_SCALAR,
-        PropertyScope::DEV_OUTPUT,
-    );
 
-    // or...
+    // the type of a typed selector is checked at compile time...
+    for channel in device.output_channels() {
+        let volume = device.get_element(VOLUME_SCALAR, *channel)?;
+        println!("Volume on channel #{channel}: {:.02}%", volume);
+    }
+
+    // ...while a raw address relies on the caller picking the right one
     let mut address = AudioObjPropAddress::new(
         PropertySelector::Device(AudioDevPropSelector::VolumeScalar),
         PropertyScope::DEV_OUTPUT,
@@ -22,7 +25,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     for channel in device.output_channels() {
         address.set_element(*channel);
 
-        let volume = device.get_property::<String>(address)?;
+        let volume = device.get_property::<f32>(address)?;
         println!("Volume on channel #{channel}: {:.02}%", volume);
     }
 
@@ -1,5 +1,8 @@
-use crate::{mscope::PropertyScope, mselector::PropertySelector};
-use coreaudio_sys::AudioObjectPropertyAddress;
+use crate::{
+    error::Error, mscope::PropertyScope, mselector::PropertySelector,
+    sys::AudioObjectPropertyAddress,
+};
+use std::fmt::{self, Display, Formatter};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct AudioObjPropAddress {
@@ -51,3 +54,25 @@ impl From<AudioObjPropAddress> for AudioObjectPropertyAddress {
         }
     }
 }
+
+impl TryFrom<AudioObjectPropertyAddress> for AudioObjPropAddress {
+    type Error = Error;
+
+    fn try_from(value: AudioObjectPropertyAddress) -> Result<Self, Self::Error> {
+        Ok(Self::new_with_element(
+            value.mSelector.try_into()?,
+            value.mScope.try_into()?,
+            value.mElement,
+        ))
+    }
+}
+
+impl Display for AudioObjPropAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, element {})",
+            self.select, self.scope, self.element
+        )
+    }
+}
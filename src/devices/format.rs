@@ -0,0 +1,250 @@
+use crate::{
+    property::{AudioStreamBasicDescription, AudioStreamRangedDescription},
+    sys::{
+        kAudioFormat60958AC3, kAudioFormatAC3, kAudioFormatFlagIsAlignedHigh,
+        kAudioFormatFlagIsBigEndian, kAudioFormatFlagIsFloat, kAudioFormatFlagIsNonInterleaved,
+        kAudioFormatFlagIsNonMixable, kAudioFormatFlagIsPacked, kAudioFormatFlagIsSignedInteger,
+        kAudioFormatLinearPCM,
+    },
+};
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormatId {
+    LinearPcm,
+    Ac3,
+    /// AC-3 packaged for transport over an IEC 60958 (S/PDIF) interface.
+    Iec60958Ac3,
+    Other(u32),
+}
+
+impl FormatId {
+    const KNOWN: [Self; 3] = [Self::LinearPcm, Self::Ac3, Self::Iec60958Ac3];
+
+    pub fn from_raw(value: u32) -> Self {
+        Self::KNOWN
+            .into_iter()
+            .find(|id| id.raw() == value)
+            .unwrap_or(Self::Other(value))
+    }
+
+    pub const fn raw(self) -> u32 {
+        match self {
+            Self::LinearPcm => kAudioFormatLinearPCM,
+            Self::Ac3 => kAudioFormatAC3,
+            Self::Iec60958Ac3 => kAudioFormat60958AC3,
+            Self::Other(value) => value,
+        }
+    }
+}
+
+/// The generic `kAudioFormatFlag*` flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FormatFlag {
+    Float,
+    BigEndian,
+    SignedInteger,
+    Packed,
+    AlignedHigh,
+    NonInterleaved,
+    NonMixable,
+}
+
+impl FormatFlag {
+    pub const ALL: [Self; 7] = [
+        Self::Float,
+        Self::BigEndian,
+        Self::SignedInteger,
+        Self::Packed,
+        Self::AlignedHigh,
+        Self::NonInterleaved,
+        Self::NonMixable,
+    ];
+
+    pub const fn bit(self) -> u32 {
+        match self {
+            Self::Float => kAudioFormatFlagIsFloat,
+            Self::BigEndian => kAudioFormatFlagIsBigEndian,
+            Self::SignedInteger => kAudioFormatFlagIsSignedInteger,
+            Self::Packed => kAudioFormatFlagIsPacked,
+            Self::AlignedHigh => kAudioFormatFlagIsAlignedHigh,
+            Self::NonInterleaved => kAudioFormatFlagIsNonInterleaved,
+            Self::NonMixable => kAudioFormatFlagIsNonMixable,
+        }
+    }
+}
+
+/// A set of [`FormatFlag`]s.
+///
+/// Format specific bits that have no [`FormatFlag`] (e.g. the fixed-point
+/// fraction of linear PCM) are kept as they are, so converting back to the raw
+/// value is lossless.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FormatFlags(u32);
+
+impl FormatFlags {
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn from_raw(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn raw(self) -> u32 {
+        self.0
+    }
+
+    pub const fn contains(self, flag: FormatFlag) -> bool {
+        self.0 & flag.bit() != 0
+    }
+
+    pub fn insert(&mut self, flag: FormatFlag) {
+        self.0 |= flag.bit();
+    }
+
+    pub fn remove(&mut self, flag: FormatFlag) {
+        self.0 &= !flag.bit();
+    }
+
+    pub fn with(mut self, flag: FormatFlag) -> Self {
+        self.insert(flag);
+        self
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = FormatFlag> {
+        FormatFlag::ALL
+            .into_iter()
+            .filter(move |flag| self.contains(*flag))
+    }
+}
+
+impl FromIterator<FormatFlag> for FormatFlags {
+    fn from_iter<I: IntoIterator<Item = FormatFlag>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::empty(), Self::with)
+    }
+}
+
+/// Safe model of an `AudioStreamBasicDescription`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamFormat {
+    pub sample_rate: f64,
+    pub format_id: FormatId,
+    pub flags: FormatFlags,
+    pub bytes_per_packet: u32,
+    pub frames_per_packet: u32,
+    pub bytes_per_frame: u32,
+    pub channels_per_frame: u32,
+    pub bits_per_channel: u32,
+}
+
+impl StreamFormat {
+    /// Packed linear PCM with `bits` per sample, stored as floats or as signed
+    /// integers.
+    pub fn linear_pcm(
+        sample_rate: f64,
+        channels: u32,
+        bits: u32,
+        float: bool,
+        interleaved: bool,
+    ) -> Self {
+        let mut flags = FormatFlags::empty().with(FormatFlag::Packed);
+        flags.insert(if float {
+            FormatFlag::Float
+        } else {
+            FormatFlag::SignedInteger
+        });
+
+        // Non-interleaved buffers each carry a single channel.
+        let bytes_per_frame = if interleaved {
+            bits / 8 * channels
+        } else {
+            flags.insert(FormatFlag::NonInterleaved);
+            bits / 8
+        };
+
+        Self {
+            sample_rate,
+            format_id: FormatId::LinearPcm,
+            flags,
+            bytes_per_packet: bytes_per_frame,
+            frames_per_packet: 1,
+            bytes_per_frame,
+            channels_per_frame: channels,
+            bits_per_channel: bits,
+        }
+    }
+
+    pub const fn is_interleaved(&self) -> bool {
+        !self.flags.contains(FormatFlag::NonInterleaved)
+    }
+
+    pub const fn is_float(&self) -> bool {
+        self.flags.contains(FormatFlag::Float)
+    }
+
+    pub const fn is_mixable(&self) -> bool {
+        !self.flags.contains(FormatFlag::NonMixable)
+    }
+}
+
+impl From<AudioStreamBasicDescription> for StreamFormat {
+    fn from(value: AudioStreamBasicDescription) -> Self {
+        Self {
+            sample_rate: value.mSampleRate,
+            format_id: FormatId::from_raw(value.mFormatID),
+            flags: FormatFlags::from_raw(value.mFormatFlags),
+            bytes_per_packet: value.mBytesPerPacket,
+            frames_per_packet: value.mFramesPerPacket,
+            bytes_per_frame: value.mBytesPerFrame,
+            channels_per_frame: value.mChannelsPerFrame,
+            bits_per_channel: value.mBitsPerChannel,
+        }
+    }
+}
+
+impl From<StreamFormat> for AudioStreamBasicDescription {
+    fn from(value: StreamFormat) -> Self {
+        Self {
+            mSampleRate: value.sample_rate,
+            mFormatID: value.format_id.raw(),
+            mFormatFlags: value.flags.raw(),
+            mBytesPerPacket: value.bytes_per_packet,
+            mFramesPerPacket: value.frames_per_packet,
+            mBytesPerFrame: value.bytes_per_frame,
+            mChannelsPerFrame: value.channels_per_frame,
+            mBitsPerChannel: value.bits_per_channel,
+            mReserved: 0,
+        }
+    }
+}
+
+/// A format a stream supports, over a range of sample rates.
+///
+/// A `sample_rate` of `0.0` in `format` means any rate in `sample_rates`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangedStreamFormat {
+    pub format: StreamFormat,
+    pub sample_rates: RangeInclusive<f64>,
+}
+
+impl RangedStreamFormat {
+    /// The format at `sample_rate`, if it lies within the supported range.
+    pub fn at_sample_rate(&self, sample_rate: f64) -> Option<StreamFormat> {
+        self.sample_rates
+            .contains(&sample_rate)
+            .then_some(StreamFormat {
+                sample_rate,
+                ..self.format
+            })
+    }
+}
+
+impl From<AudioStreamRangedDescription> for RangedStreamFormat {
+    fn from(value: AudioStreamRangedDescription) -> Self {
+        Self {
+            format: value.mFormat.into(),
+            sample_rates: value.mSampleRateRange.mMinimum..=value.mSampleRateRange.mMaximum,
+        }
+    }
+}
@@ -1,13 +1,13 @@
-use super::AudioDevice;
-use std::ops::Deref;
+use super::{
+    scoped::{InputScope, ScopedDevice},
+    AudioDevice,
+};
+use crate::error::Error;
 
-#[derive(Debug)]
-pub struct AudioInputDevice(pub(crate) AudioDevice);
+pub type AudioInputDevice = ScopedDevice<InputScope>;
 
-impl Deref for AudioInputDevice {
-    type Target = AudioDevice;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl AudioInputDevice {
+    pub fn is_default(&self) -> Result<bool, Error> {
+        Ok(AudioDevice::default_input_device_id()? == self.id())
     }
 }
@@ -0,0 +1,283 @@
+use super::AudioDevice;
+use crate::{
+    error::{Error, HalError},
+    internals,
+    mscope::PropertyScope,
+    property::{PREFERRED_CHANNEL_LAYOUT, PREFERRED_STEREO_CHANNELS},
+    sys::{
+        kAudioChannelLabel_Center, kAudioChannelLabel_CenterSurround,
+        kAudioChannelLabel_Discrete_0, kAudioChannelLabel_LFE2, kAudioChannelLabel_LFEScreen,
+        kAudioChannelLabel_Left, kAudioChannelLabel_LeftCenter, kAudioChannelLabel_LeftSurround,
+        kAudioChannelLabel_LeftSurroundDirect, kAudioChannelLabel_LeftWide,
+        kAudioChannelLabel_Mono, kAudioChannelLabel_RearSurroundLeft,
+        kAudioChannelLabel_RearSurroundRight, kAudioChannelLabel_Right,
+        kAudioChannelLabel_RightCenter, kAudioChannelLabel_RightSurround,
+        kAudioChannelLabel_RightSurroundDirect, kAudioChannelLabel_RightWide,
+        kAudioChannelLabel_TopBackCenter, kAudioChannelLabel_TopBackLeft,
+        kAudioChannelLabel_TopBackRight, kAudioChannelLabel_TopCenterSurround,
+        kAudioChannelLabel_Unknown, kAudioChannelLabel_Unused, kAudioChannelLabel_UseCoordinates,
+        kAudioChannelLabel_VerticalHeightCenter, kAudioChannelLabel_VerticalHeightLeft,
+        kAudioChannelLabel_VerticalHeightRight, kAudioChannelLayoutTag_UseChannelBitmap,
+        kAudioChannelLayoutTag_UseChannelDescriptions, kAudioHardwareBadPropertySizeError,
+        OSStatus,
+    },
+};
+
+/// `mChannelLayoutTag`, `mChannelBitmap` and `mNumberChannelDescriptions`.
+const LAYOUT_HEADER_LEN: usize = 12;
+/// `mChannelLabel`, `mChannelFlags` and three `Float32` coordinates.
+const DESCRIPTION_LEN: usize = 20;
+const BITMAP_LABELS: u32 = 18;
+
+/// The role of a channel (`kAudioChannelLabel_*`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelLabel {
+    Unknown,
+    Unused,
+    /// Positioned by coordinates instead of a label.
+    UseCoordinates,
+    Left,
+    Right,
+    Center,
+    LfeScreen,
+    LeftSurround,
+    RightSurround,
+    LeftCenter,
+    RightCenter,
+    CenterSurround,
+    LeftSurroundDirect,
+    RightSurroundDirect,
+    TopCenterSurround,
+    VerticalHeightLeft,
+    VerticalHeightCenter,
+    VerticalHeightRight,
+    TopBackLeft,
+    TopBackCenter,
+    TopBackRight,
+    RearSurroundLeft,
+    RearSurroundRight,
+    LeftWide,
+    RightWide,
+    Lfe2,
+    Mono,
+    /// A channel without a spatial role, numbered from 0.
+    Discrete(u16),
+    Other(u32),
+}
+
+impl ChannelLabel {
+    const KNOWN: [Self; 27] = [
+        Self::Unknown,
+        Self::Unused,
+        Self::UseCoordinates,
+        Self::Left,
+        Self::Right,
+        Self::Center,
+        Self::LfeScreen,
+        Self::LeftSurround,
+        Self::RightSurround,
+        Self::LeftCenter,
+        Self::RightCenter,
+        Self::CenterSurround,
+        Self::LeftSurroundDirect,
+        Self::RightSurroundDirect,
+        Self::TopCenterSurround,
+        Self::VerticalHeightLeft,
+        Self::VerticalHeightCenter,
+        Self::VerticalHeightRight,
+        Self::TopBackLeft,
+        Self::TopBackCenter,
+        Self::TopBackRight,
+        Self::RearSurroundLeft,
+        Self::RearSurroundRight,
+        Self::LeftWide,
+        Self::RightWide,
+        Self::Lfe2,
+        Self::Mono,
+    ];
+
+    pub fn from_raw(value: u32) -> Self {
+        if value & !0xFFFF == kAudioChannelLabel_Discrete_0 {
+            return Self::Discrete(value as u16);
+        }
+
+        Self::KNOWN
+            .into_iter()
+            .find(|label| label.raw() == value)
+            .unwrap_or(Self::Other(value))
+    }
+
+    pub const fn raw(self) -> u32 {
+        match self {
+            Self::Unknown => kAudioChannelLabel_Unknown,
+            Self::Unused => kAudioChannelLabel_Unused,
+            Self::UseCoordinates => kAudioChannelLabel_UseCoordinates,
+            Self::Left => kAudioChannelLabel_Left,
+            Self::Right => kAudioChannelLabel_Right,
+            Self::Center => kAudioChannelLabel_Center,
+            Self::LfeScreen => kAudioChannelLabel_LFEScreen,
+            Self::LeftSurround => kAudioChannelLabel_LeftSurround,
+            Self::RightSurround => kAudioChannelLabel_RightSurround,
+            Self::LeftCenter => kAudioChannelLabel_LeftCenter,
+            Self::RightCenter => kAudioChannelLabel_RightCenter,
+            Self::CenterSurround => kAudioChannelLabel_CenterSurround,
+            Self::LeftSurroundDirect => kAudioChannelLabel_LeftSurroundDirect,
+            Self::RightSurroundDirect => kAudioChannelLabel_RightSurroundDirect,
+            Self::TopCenterSurround => kAudioChannelLabel_TopCenterSurround,
+            Self::VerticalHeightLeft => kAudioChannelLabel_VerticalHeightLeft,
+            Self::VerticalHeightCenter => kAudioChannelLabel_VerticalHeightCenter,
+            Self::VerticalHeightRight => kAudioChannelLabel_VerticalHeightRight,
+            Self::TopBackLeft => kAudioChannelLabel_TopBackLeft,
+            Self::TopBackCenter => kAudioChannelLabel_TopBackCenter,
+            Self::TopBackRight => kAudioChannelLabel_TopBackRight,
+            Self::RearSurroundLeft => kAudioChannelLabel_RearSurroundLeft,
+            Self::RearSurroundRight => kAudioChannelLabel_RearSurroundRight,
+            Self::LeftWide => kAudioChannelLabel_LeftWide,
+            Self::RightWide => kAudioChannelLabel_RightWide,
+            Self::Lfe2 => kAudioChannelLabel_LFE2,
+            Self::Mono => kAudioChannelLabel_Mono,
+            Self::Discrete(index) => kAudioChannelLabel_Discrete_0 | index as u32,
+            Self::Other(value) => value,
+        }
+    }
+}
+
+/// A decoded `AudioChannelLayout`.
+///
+/// Layouts described by channel descriptions or by a channel bitmap are
+/// decoded into one label per channel. Layouts given only by a predefined
+/// layout tag keep `channels` empty; the tag is available in `tag`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelLayout {
+    pub tag: u32,
+    pub channels: Vec<ChannelLabel>,
+}
+
+impl ChannelLayout {
+    pub fn from_labels(labels: &[ChannelLabel]) -> Self {
+        Self {
+            tag: kAudioChannelLayoutTag_UseChannelDescriptions,
+            channels: labels.to_vec(),
+        }
+    }
+
+    /// Parses an `AudioChannelLayout` including its trailing descriptions array.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        let word = |offset: usize| -> Result<u32, Error> {
+            data.get(offset..offset + 4)
+                .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+                .ok_or(Error::InvalidChannelLayout)
+        };
+
+        let tag = word(0)?;
+        let bitmap = word(4)?;
+        let count = usize::try_from(word(8)?)?;
+
+        let channels = if tag == kAudioChannelLayoutTag_UseChannelDescriptions {
+            let end = count
+                .checked_mul(DESCRIPTION_LEN)
+                .and_then(|len| len.checked_add(LAYOUT_HEADER_LEN))
+                .ok_or(Error::InvalidChannelLayout)?;
+            if data.len() < end {
+                return Err(Error::InvalidChannelLayout);
+            }
+
+            (0..count)
+                .map(|index| word(LAYOUT_HEADER_LEN + index * DESCRIPTION_LEN))
+                .map(|label| label.map(ChannelLabel::from_raw))
+                .collect::<Result<_, _>>()?
+        } else if tag == kAudioChannelLayoutTag_UseChannelBitmap {
+            (0..BITMAP_LABELS)
+                .filter(|bit| bitmap & (1 << bit) != 0)
+                .map(|bit| ChannelLabel::from_raw(bit + 1))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self { tag, channels })
+    }
+
+    /// Encodes the layout as an `AudioChannelLayout` with channel descriptions.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data =
+            Vec::with_capacity(LAYOUT_HEADER_LEN + self.channels.len() * DESCRIPTION_LEN);
+
+        data.extend_from_slice(&kAudioChannelLayoutTag_UseChannelDescriptions.to_ne_bytes());
+        data.extend_from_slice(&0u32.to_ne_bytes());
+        data.extend_from_slice(&(self.channels.len() as u32).to_ne_bytes());
+
+        for label in &self.channels {
+            data.extend_from_slice(&label.raw().to_ne_bytes());
+            data.resize(data.len() + DESCRIPTION_LEN - 4, 0);
+        }
+
+        data
+    }
+
+    /// Label of the device channel `element` (numbered from 1).
+    pub fn label(&self, element: u32) -> Option<ChannelLabel> {
+        let index = usize::try_from(element.checked_sub(1)?).ok()?;
+
+        self.channels.get(index).copied()
+    }
+
+    /// Device channel (numbered from 1) carrying `label`.
+    pub fn element(&self, label: ChannelLabel) -> Option<u32> {
+        let index = self.channels.iter().position(|channel| *channel == label)?;
+
+        u32::try_from(index + 1).ok()
+    }
+}
+
+impl AudioDevice {
+    /// The channels, numbered from 1, that play or record the left and right
+    /// side of stereo audio.
+    pub fn preferred_stereo_channels(&self, scope: PropertyScope) -> Result<[u32; 2], Error> {
+        let address = PREFERRED_STEREO_CHANNELS.address(scope, 0);
+        let channels: Vec<u32> = internals::get_property(self.device_id, address)?;
+
+        match channels[..] {
+            [left, right] => Ok([left, right]),
+            _ => Err(HalError::for_property(
+                kAudioHardwareBadPropertySizeError as OSStatus,
+                self.device_id,
+                address,
+            )
+            .into()),
+        }
+    }
+
+    pub fn set_preferred_stereo_channels(
+        &self,
+        scope: PropertyScope,
+        [left, right]: [u32; 2],
+    ) -> Result<(), Error> {
+        if let Some(channel) = [left, right]
+            .into_iter()
+            .find(|channel| *channel == 0 || !self.has_channel(scope, *channel))
+        {
+            return Err(Error::InvalidChannel(channel));
+        }
+
+        internals::set_property(
+            self.device_id,
+            PREFERRED_STEREO_CHANNELS.address(scope, 0),
+            &vec![left, right],
+        )
+    }
+
+    pub fn preferred_channel_layout(&self, scope: PropertyScope) -> Result<ChannelLayout, Error> {
+        let data: Vec<u8> =
+            internals::get_property(self.device_id, PREFERRED_CHANNEL_LAYOUT.address(scope, 0))?;
+
+        ChannelLayout::from_bytes(&data)
+    }
+
+    /// Devices that report no channels in `scope` accept any channel number.
+    fn has_channel(&self, scope: PropertyScope, channel: u32) -> bool {
+        let channels = self.channels(scope);
+
+        channels.is_empty() || channels.contains(&channel)
+    }
+}
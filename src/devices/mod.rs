@@ -1,21 +1,49 @@
 use crate::{
     aopa::AudioObjPropAddress,
     error::Error,
-    internals::{self, get_property_complex, get_property_data_size},
+    internals,
     mscope::PropertyScope,
     mselector::PropertySelector,
+    property::{PropertyValue, TypedSelector, DEVICE_UID, MANUFACTURER, MODEL_UID, NAME},
+    sys::{kAudioObjectSystemObject, AudioDeviceID, AudioStreamID},
 };
-use coreaudio_sys::{kAudioObjectSystemObject, AudioDeviceID};
+pub use format::{FormatFlag, FormatFlags, FormatId, RangedStreamFormat, StreamFormat};
 pub use input::AudioInputDevice;
+pub use layout::{ChannelLabel, ChannelLayout};
 pub use output::AudioOutputDevice;
-use std::mem::size_of;
+pub use scoped::{
+    AudioPlayThroughDevice, DeviceScope, InputScope, OutputScope, PlayThroughScope, ScopedDevice,
+};
+use std::{
+    hash::{Hash, Hasher},
+    iter,
+};
+pub use stream::{AudioStream, StreamDirection, TerminalType};
+pub use timing::{Latency, LatencyReport};
+pub use transport::TransportType;
+pub use volume::{
+    decibels_to_scalar, scalar_to_decibels, VolumeControl, VolumeControlKind, VolumeMode,
+};
 
+mod format;
 mod input;
+mod layout;
 mod output;
+mod scoped;
+mod stream;
+mod timing;
+mod transport;
+mod volume;
 
+/// An audio device.
+///
+/// Two devices compare equal when they have the same persistent UID, so a
+/// device that was reconnected (and got a new [`AudioDeviceID`]) is still equal
+/// to the one seen before. Devices without a UID fall back to comparing IDs.
 #[derive(Debug)]
 pub struct AudioDevice {
     device_id: AudioDeviceID,
+    uid: Option<Box<str>>,
     name: Box<str>,
     input_channels: Box<[u32]>,
     output_channels: Box<[u32]>,
@@ -25,32 +53,41 @@ pub struct AudioDevice {
 
 impl AudioDevice {
     pub fn from_id(id: AudioDeviceID) -> Result<Self, Error> {
-        let name = internals::get_device_name(id, PropertyScope::DEV_OUTPUT)?;
-        let input_channels = internals::get_valid_channels(id, PropertyScope::DEV_INPUT);
-        let output_channels = internals::get_valid_channels(id, PropertyScope::DEV_OUTPUT);
+        let name = internals::get_device_name(id)?;
+        let uid =
+            internals::get_property::<String>(id, DEVICE_UID.address(PropertyScope::OBJ_GLOBAL, 0))
+                .ok();
+        let input_channels = internals::get_channel_count(id, PropertyScope::DEV_INPUT)?;
+        let output_channels = internals::get_channel_count(id, PropertyScope::DEV_OUTPUT)?;
         let input_streams = internals::get_streams(id, PropertyScope::DEV_INPUT)?;
         let output_streams = internals::get_streams(id, PropertyScope::DEV_OUTPUT)?;
 
         Ok(Self {
             device_id: id,
+            uid: uid.map(Into::into),
             name: name.into(),
-            input_channels: input_channels.into(),
-            output_channels: output_channels.into(),
+            input_channels: (1..=input_channels).collect(),
+            output_channels: (1..=output_channels).collect(),
             input_streams,
             output_streams,
         })
     }
 
+    /// Opens the device with the persistent UID `uid`.
+    pub fn from_uid(uid: &str) -> Result<Self, Error> {
+        find_by_uid(uid)?.ok_or_else(|| Error::UnknownDeviceUid(uid.into()))
+    }
+
     pub fn default_input() -> Result<AudioInputDevice, Error> {
         let input = Self::from_id(Self::default_input_device_id()?)?;
 
-        Ok(unsafe { input.as_input().unwrap_unchecked() })
+        input.as_input().ok_or(Error::NotInput)
     }
 
     pub fn default_output() -> Result<AudioOutputDevice, Error> {
         let output_dev = Self::from_id(Self::default_output_device_id()?)?;
 
-        Ok(unsafe { output_dev.as_output().unwrap_unchecked() })
+        output_dev.as_output().ok_or(Error::NotOutput)
     }
 
     pub fn as_output(self) -> Option<AudioOutputDevice> {
@@ -58,7 +95,7 @@ impl AudioDevice {
             return None;
         }
 
-        Some(AudioOutputDevice(self))
+        Some(ScopedDevice::new(self))
     }
 
     pub fn as_input(self) -> Option<AudioInputDevice> {
@@ -66,7 +103,16 @@ impl AudioDevice {
             return None;
         }
 
-        Some(AudioInputDevice(self))
+        Some(ScopedDevice::new(self))
+    }
+
+    /// The play-through controls of a device with both inputs and outputs.
+    pub fn as_play_through(self) -> Option<AudioPlayThroughDevice> {
+        if !self.is_multi() {
+            return None;
+        }
+
+        Some(ScopedDevice::new(self))
     }
 
     pub const fn id(&self) -> AudioDeviceID {
@@ -77,14 +123,61 @@ impl AudioDevice {
         &self.name
     }
 
+    /// Renames the device, for devices that allow it (e.g. aggregate devices).
+    pub fn set_name(&mut self, name: &str) -> Result<(), Error> {
+        self.set_string(NAME, name)?;
+        self.name = name.into();
+
+        Ok(())
+    }
+
+    /// Persistent identifier of the device, stable across reboots and reconnects.
+    pub fn uid(&self) -> Result<String, Error> {
+        self.get_global(DEVICE_UID)
+    }
+
+    /// Identifier shared by all devices of the same model.
+    pub fn model_uid(&self) -> Result<String, Error> {
+        self.get_global(MODEL_UID)
+    }
+
+    pub fn manufacturer(&self) -> Result<String, Error> {
+        self.get_global(MANUFACTURER)
+    }
+
+    /// Input channels, numbered from 1 across all input streams.
     pub const fn input_channels(&self) -> &[u32] {
         &self.input_channels
     }
 
+    /// Output channels, numbered from 1 across all output streams.
     pub const fn output_channels(&self) -> &[u32] {
         &self.output_channels
     }
 
+    pub(crate) fn channels(&self, scope: PropertyScope) -> &[u32] {
+        match scope {
+            PropertyScope::DEV_INPUT | PropertyScope::DEV_PLAY_THROUGH => &self.input_channels,
+            _ => &self.output_channels,
+        }
+    }
+
+    /// Elements in `scope` that have a volume or mute control, including the
+    /// master element 0. Not every channel has its own controls.
+    pub fn controllable_channels(&self, scope: PropertyScope) -> Vec<u32> {
+        iter::once(0)
+            .chain(self.channels(scope).iter().copied())
+            .filter(|element| {
+                [
+                    PropertySelector::DEV_VOLUME_SCALAR,
+                    PropertySelector::DEV_MUTE,
+                ]
+                .into_iter()
+                .any(|selector| self.has_control(selector, scope, *element))
+            })
+            .collect()
+    }
+
     pub const fn is_input(&self) -> bool {
         !self.input_channels.is_empty()
     }
@@ -105,7 +198,62 @@ impl AudioDevice {
         self.output_streams
     }
 
-    fn default_input_device_id() -> Result<AudioDeviceID, Error> {
+    /// The streams of the device in `scope`, ordered by starting channel.
+    pub fn streams(&self, scope: PropertyScope) -> Result<Vec<AudioStream>, Error> {
+        let ids: Vec<AudioStreamID> = internals::get_property_array(
+            self.device_id,
+            AudioObjPropAddress::new(PropertySelector::DEV_STREAMS, scope),
+        )?;
+
+        let mut streams = ids
+            .into_iter()
+            .map(AudioStream::from_id)
+            .map(|stream| Ok((stream.starting_channel()?, stream)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        streams.sort_by_key(|(channel, _)| *channel);
+
+        Ok(streams.into_iter().map(|(_, stream)| stream).collect())
+    }
+
+    /// IDs of the data sources (e.g. internal speakers vs. headphones) available in `scope`.
+    pub fn data_sources(&self, scope: PropertyScope) -> Result<Vec<u32>, Error> {
+        internals::get_property_array(
+            self.device_id,
+            AudioObjPropAddress::new(PropertySelector::DEV_DATA_SOURCES, scope),
+        )
+    }
+
+    /// ID of the currently selected data source in `scope`.
+    pub fn data_source(&self, scope: PropertyScope) -> Result<u32, Error> {
+        internals::get_property(
+            self.device_id,
+            AudioObjPropAddress::new(PropertySelector::DEV_DATA_SOURCE, scope),
+        )
+    }
+
+    fn has_control(&self, selector: PropertySelector, scope: PropertyScope, element: u32) -> bool {
+        internals::has_property(
+            self.device_id,
+            AudioObjPropAddress::new_with_element(selector, scope, element),
+        )
+    }
+
+    fn get_global<T: PropertyValue>(&self, selector: TypedSelector<T>) -> Result<T, Error> {
+        internals::get_property(
+            self.device_id,
+            selector.address(PropertyScope::OBJ_GLOBAL, 0),
+        )
+    }
+
+    fn set_string(&self, selector: TypedSelector<String>, value: &str) -> Result<(), Error> {
+        internals::set_property(
+            self.device_id,
+            selector.address(PropertyScope::OBJ_GLOBAL, 0),
+            &value.to_owned(),
+        )
+    }
+
+    pub(crate) fn default_input_device_id() -> Result<AudioDeviceID, Error> {
         internals::get_property(
             kAudioObjectSystemObject,
             AudioObjPropAddress::new(
@@ -126,20 +274,44 @@ impl AudioDevice {
     }
 }
 
-pub fn get_all_devices() -> Result<Vec<AudioDevice>, Error> {
-    let address =
-        AudioObjPropAddress::new(PropertySelector::HW_ALL_DEVICES, PropertyScope::OBJ_GLOBAL);
+impl PartialEq for AudioDevice {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.uid, &other.uid) {
+            (Some(uid), Some(other_uid)) => uid == other_uid,
+            (None, None) => self.device_id == other.device_id,
+            _ => false,
+        }
+    }
+}
 
-    let size = get_property_data_size(kAudioObjectSystemObject, address)?;
-    let n_devices = size / size_of::<AudioDeviceID>();
-    let mut devices: Vec<AudioDeviceID> = vec![0; n_devices];
+impl Eq for AudioDevice {}
 
-    get_property_complex(
-        kAudioObjectSystemObject,
-        address,
-        devices.as_mut_ptr(),
-        size,
-    )?;
+impl Hash for AudioDevice {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match &self.uid {
+            Some(uid) => uid.hash(state),
+            None => self.device_id.hash(state),
+        }
+    }
+}
+
+/// Finds the connected device with the persistent UID `uid`.
+pub fn find_by_uid(uid: &str) -> Result<Option<AudioDevice>, Error> {
+    internals::translate_uid(uid)?
+        .map(AudioDevice::from_id)
+        .transpose()
+}
 
-    devices.into_iter().map(AudioDevice::from_id).collect()
+pub fn get_all_devices() -> Result<Vec<AudioDevice>, Error> {
+    get_all_device_ids()?
+        .into_iter()
+        .map(AudioDevice::from_id)
+        .collect()
+}
+
+pub fn get_all_device_ids() -> Result<Vec<AudioDeviceID>, Error> {
+    internals::get_property_array(
+        kAudioObjectSystemObject,
+        AudioObjPropAddress::new(PropertySelector::HW_ALL_DEVICES, PropertyScope::OBJ_GLOBAL),
+    )
 }
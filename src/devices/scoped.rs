@@ -0,0 +1,220 @@
+use super::{
+    volume::{VolumeControl, VolumeMode},
+    AudioDevice,
+};
+use crate::{
+    aopa::AudioObjPropAddress,
+    error::Error,
+    events::{
+        listener::Subscription,
+        volume::{self, MuteChanged, VolumeChanged},
+    },
+    internals,
+    mscope::PropertyScope,
+    property::{PropertyValue, TypedSelector, DATA_SOURCE},
+};
+use std::{marker::PhantomData, ops::Deref};
+
+/// A device scope known at compile time, see [`ScopedDevice`].
+pub trait DeviceScope {
+    const SCOPE: PropertyScope;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct InputScope;
+
+#[derive(Debug, Clone, Copy)]
+pub struct OutputScope;
+
+/// The scope of the controls that route input directly to the output.
+#[derive(Debug, Clone, Copy)]
+pub struct PlayThroughScope;
+
+impl DeviceScope for InputScope {
+    const SCOPE: PropertyScope = PropertyScope::DEV_INPUT;
+}
+
+impl DeviceScope for OutputScope {
+    const SCOPE: PropertyScope = PropertyScope::DEV_OUTPUT;
+}
+
+impl DeviceScope for PlayThroughScope {
+    const SCOPE: PropertyScope = PropertyScope::DEV_PLAY_THROUGH;
+}
+
+pub type AudioPlayThroughDevice = ScopedDevice<PlayThroughScope>;
+
+/// A device together with one of its scopes.
+///
+/// Generic code can take any directional device as a `ScopedDevice<S>`. The
+/// usual ones are [`AudioInputDevice`](super::AudioInputDevice),
+/// [`AudioOutputDevice`](super::AudioOutputDevice) and
+/// [`AudioPlayThroughDevice`].
+#[derive(Debug)]
+pub struct ScopedDevice<S: DeviceScope> {
+    device: AudioDevice,
+    scope: PhantomData<S>,
+}
+
+impl<S: DeviceScope> ScopedDevice<S> {
+    pub(crate) const fn new(device: AudioDevice) -> Self {
+        Self {
+            device,
+            scope: PhantomData,
+        }
+    }
+
+    pub const fn scope(&self) -> PropertyScope {
+        S::SCOPE
+    }
+
+    /// Channels of the device in this scope.
+    pub fn channels(&self) -> &[u32] {
+        self.device.channels(S::SCOPE)
+    }
+
+    /// Which volume and mute controls the device has in this scope.
+    pub fn volume_control(&self) -> Result<VolumeControl, Error> {
+        self.device.volume_control(S::SCOPE)
+    }
+
+    pub fn avg_volume(&self) -> Result<f32, Error> {
+        self.avg_volume_in(S::SCOPE)
+    }
+
+    pub fn volume_for_channel(&self, ch: u32) -> Result<f32, Error> {
+        self.volume_for_channel_in(S::SCOPE, ch)
+    }
+
+    /// Sets every channel to `vol`, see [`set_volume_with`](Self::set_volume_with)
+    /// to keep the balance between channels.
+    pub fn set_volume(&self, vol: f32) -> Result<(), Error> {
+        self.set_volume_with(vol, VolumeMode::Uniform)
+    }
+
+    pub fn set_volume_with(&self, vol: f32, mode: VolumeMode) -> Result<(), Error> {
+        self.set_volume_in(S::SCOPE, vol, mode)
+    }
+
+    pub fn set_volume_for_channel(&self, ch: u32, vol: f32) -> Result<(), Error> {
+        self.set_volume_for_channel_in(S::SCOPE, ch, vol)
+    }
+
+    /// Left/right balance of the stereo channels, from -1.0 (left only) to
+    /// 1.0 (right only).
+    pub fn balance(&self) -> Result<f32, Error> {
+        self.balance_in(S::SCOPE)
+    }
+
+    /// Changes the balance while keeping the overall loudness, unless the
+    /// louder side would have to go past full volume.
+    pub fn set_balance(&self, balance: f32) -> Result<(), Error> {
+        self.set_balance_in(S::SCOPE, balance)
+    }
+
+    pub fn volume_db_for_channel(&self, ch: u32) -> Result<f32, Error> {
+        self.volume_db_for_channel_in(S::SCOPE, ch)
+    }
+
+    /// Sets every channel to `db` decibels, which must lie within the
+    /// [`decibel_range`](AudioDevice::decibel_range) of each channel.
+    pub fn set_volume_db(&self, db: f32) -> Result<(), Error> {
+        self.set_volume_db_in(S::SCOPE, db)
+    }
+
+    pub fn muted(&self) -> Result<bool, Error> {
+        self.muted_in(S::SCOPE)
+    }
+
+    pub fn set_mute(&self, mute: bool) -> Result<(), Error> {
+        self.set_mute_in(S::SCOPE, mute)
+    }
+
+    /// IDs of the data sources available in this scope.
+    pub fn data_sources(&self) -> Result<Vec<u32>, Error> {
+        self.device.data_sources(S::SCOPE)
+    }
+
+    pub fn data_source(&self) -> Result<u32, Error> {
+        self.device.data_source(S::SCOPE)
+    }
+
+    pub fn set_data_source(&self, source: u32) -> Result<(), Error> {
+        self.set(DATA_SOURCE, &source)
+    }
+
+    pub fn on_volume_changed<F>(&self, callback: F) -> Result<Subscription, Error>
+    where
+        F: Fn(VolumeChanged) + Send + Sync + 'static,
+    {
+        volume::on_volume_changed(self, S::SCOPE, callback)
+    }
+
+    pub fn on_mute_changed<F>(&self, callback: F) -> Result<Subscription, Error>
+    where
+        F: Fn(MuteChanged) + Send + Sync + 'static,
+    {
+        volume::on_mute_changed(self, S::SCOPE, callback)
+    }
+
+    pub fn downgrade(self) -> AudioDevice {
+        self.device
+    }
+
+    /*** --- Utils --- ***/
+
+    /// Reads a typed property from the master element of this scope.
+    pub fn get<T: PropertyValue>(&self, selector: TypedSelector<T>) -> Result<T, Error> {
+        self.get_element(selector, 0)
+    }
+
+    pub fn get_element<T: PropertyValue>(
+        &self,
+        selector: TypedSelector<T>,
+        element: u32,
+    ) -> Result<T, Error> {
+        self.get_property(selector.address(S::SCOPE, element))
+    }
+
+    /// Writes a typed property on the master element of this scope.
+    pub fn set<T: PropertyValue>(
+        &self,
+        selector: TypedSelector<T>,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.set_element(selector, 0, value)
+    }
+
+    pub fn set_element<T: PropertyValue>(
+        &self,
+        selector: TypedSelector<T>,
+        element: u32,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.set_property(selector.address(S::SCOPE, element), value)
+    }
+
+    pub fn get_property<T: PropertyValue>(&self, prop: AudioObjPropAddress) -> Result<T, Error> {
+        internals::get_property(self.device_id, prop)
+    }
+
+    pub fn set_property<T: PropertyValue>(
+        &self,
+        prop: AudioObjPropAddress,
+        value: &T,
+    ) -> Result<(), Error> {
+        internals::set_property(self.device_id, prop, value)
+    }
+
+    pub fn has_property(&self, prop: AudioObjPropAddress) -> bool {
+        internals::has_property(self.device_id, prop)
+    }
+}
+
+impl<S: DeviceScope> Deref for ScopedDevice<S> {
+    type Target = AudioDevice;
+
+    fn deref(&self) -> &Self::Target {
+        &self.device
+    }
+}
@@ -0,0 +1,191 @@
+use super::format::{RangedStreamFormat, StreamFormat};
+use crate::{
+    error::Error,
+    internals,
+    mscope::PropertyScope,
+    property::{
+        AudioStreamRangedDescription, PropertyValue, TypedSelector, AVAILABLE_PHYSICAL_FORMATS,
+        AVAILABLE_VIRTUAL_FORMATS, IS_ACTIVE, PHYSICAL_FORMAT, STARTING_CHANNEL, STREAM_DIRECTION,
+        STREAM_LATENCY, TERMINAL_TYPE, VIRTUAL_FORMAT,
+    },
+    sys::{
+        kAudioStreamTerminalTypeDigitalAudioInterface, kAudioStreamTerminalTypeDisplayPort,
+        kAudioStreamTerminalTypeHDMI, kAudioStreamTerminalTypeHeadphones,
+        kAudioStreamTerminalTypeHeadsetMicrophone, kAudioStreamTerminalTypeLFESpeaker,
+        kAudioStreamTerminalTypeLine, kAudioStreamTerminalTypeMicrophone,
+        kAudioStreamTerminalTypeReceiverMicrophone, kAudioStreamTerminalTypeReceiverSpeaker,
+        kAudioStreamTerminalTypeSpeaker, kAudioStreamTerminalTypeTTY,
+        kAudioStreamTerminalTypeUnknown, AudioStreamID,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamDirection {
+    Output,
+    Input,
+}
+
+/// What a stream is connected to. USB devices may also report the raw USB
+/// terminal type codes, which end up in [`TerminalType::Other`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TerminalType {
+    Unknown,
+    Line,
+    DigitalAudioInterface,
+    Speaker,
+    Headphones,
+    LfeSpeaker,
+    ReceiverSpeaker,
+    Microphone,
+    HeadsetMicrophone,
+    ReceiverMicrophone,
+    Tty,
+    Hdmi,
+    DisplayPort,
+    Other(u32),
+}
+
+impl TerminalType {
+    const KNOWN: [Self; 13] = [
+        Self::Unknown,
+        Self::Line,
+        Self::DigitalAudioInterface,
+        Self::Speaker,
+        Self::Headphones,
+        Self::LfeSpeaker,
+        Self::ReceiverSpeaker,
+        Self::Microphone,
+        Self::HeadsetMicrophone,
+        Self::ReceiverMicrophone,
+        Self::Tty,
+        Self::Hdmi,
+        Self::DisplayPort,
+    ];
+
+    pub fn from_raw(value: u32) -> Self {
+        Self::KNOWN
+            .into_iter()
+            .find(|kind| kind.raw() == value)
+            .unwrap_or(Self::Other(value))
+    }
+
+    pub const fn raw(self) -> u32 {
+        match self {
+            Self::Unknown => kAudioStreamTerminalTypeUnknown,
+            Self::Line => kAudioStreamTerminalTypeLine,
+            Self::DigitalAudioInterface => kAudioStreamTerminalTypeDigitalAudioInterface,
+            Self::Speaker => kAudioStreamTerminalTypeSpeaker,
+            Self::Headphones => kAudioStreamTerminalTypeHeadphones,
+            Self::LfeSpeaker => kAudioStreamTerminalTypeLFESpeaker,
+            Self::ReceiverSpeaker => kAudioStreamTerminalTypeReceiverSpeaker,
+            Self::Microphone => kAudioStreamTerminalTypeMicrophone,
+            Self::HeadsetMicrophone => kAudioStreamTerminalTypeHeadsetMicrophone,
+            Self::ReceiverMicrophone => kAudioStreamTerminalTypeReceiverMicrophone,
+            Self::Tty => kAudioStreamTerminalTypeTTY,
+            Self::Hdmi => kAudioStreamTerminalTypeHDMI,
+            Self::DisplayPort => kAudioStreamTerminalTypeDisplayPort,
+            Self::Other(value) => value,
+        }
+    }
+}
+
+/// One stream of a device, carrying a contiguous range of its channels in one
+/// direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AudioStream {
+    stream_id: AudioStreamID,
+}
+
+impl AudioStream {
+    pub const fn from_id(id: AudioStreamID) -> Self {
+        Self { stream_id: id }
+    }
+
+    pub const fn id(&self) -> AudioStreamID {
+        self.stream_id
+    }
+
+    pub fn direction(&self) -> Result<StreamDirection, Error> {
+        let direction: u32 = self.get(STREAM_DIRECTION)?;
+
+        Ok(match direction {
+            0 => StreamDirection::Output,
+            _ => StreamDirection::Input,
+        })
+    }
+
+    /// Device channel number of the stream's first channel. Channels are
+    /// numbered from 1 across all streams of the device in the same direction.
+    pub fn starting_channel(&self) -> Result<u32, Error> {
+        self.get(STARTING_CHANNEL)
+    }
+
+    pub fn terminal_type(&self) -> Result<TerminalType, Error> {
+        self.get(TERMINAL_TYPE).map(TerminalType::from_raw)
+    }
+
+    /// Inactive streams are disabled and do no I/O.
+    pub fn is_active(&self) -> Result<bool, Error> {
+        self.get(IS_ACTIVE)
+    }
+
+    /// Latency of the stream in frames, on top of the device latency.
+    pub fn latency(&self) -> Result<u32, Error> {
+        self.get(STREAM_LATENCY)
+    }
+
+    /// Format of the data exchanged with clients of the HAL.
+    pub fn virtual_format(&self) -> Result<StreamFormat, Error> {
+        self.get(VIRTUAL_FORMAT).map(StreamFormat::from)
+    }
+
+    /// Format the hardware actually runs at.
+    pub fn physical_format(&self) -> Result<StreamFormat, Error> {
+        self.get(PHYSICAL_FORMAT).map(StreamFormat::from)
+    }
+
+    pub fn available_virtual_formats(&self) -> Result<Vec<RangedStreamFormat>, Error> {
+        self.get_ranged(AVAILABLE_VIRTUAL_FORMATS)
+    }
+
+    pub fn available_physical_formats(&self) -> Result<Vec<RangedStreamFormat>, Error> {
+        self.get_ranged(AVAILABLE_PHYSICAL_FORMATS)
+    }
+
+    /// Changes the virtual format. The HAL converts between it and the
+    /// physical format.
+    pub fn set_virtual_format(&self, format: &StreamFormat) -> Result<(), Error> {
+        self.set(VIRTUAL_FORMAT, &(*format).into())
+    }
+
+    /// Switches the hardware to `format`, which should be one of
+    /// [`available_physical_formats`](Self::available_physical_formats). This
+    /// may also change the device's nominal sample rate.
+    pub fn set_physical_format(&self, format: &StreamFormat) -> Result<(), Error> {
+        self.set(PHYSICAL_FORMAT, &(*format).into())
+    }
+
+    fn get<T: PropertyValue>(&self, selector: TypedSelector<T>) -> Result<T, Error> {
+        internals::get_property(
+            self.stream_id,
+            selector.address(PropertyScope::OBJ_GLOBAL, 0),
+        )
+    }
+
+    fn set<T: PropertyValue>(&self, selector: TypedSelector<T>, value: &T) -> Result<(), Error> {
+        internals::set_property(
+            self.stream_id,
+            selector.address(PropertyScope::OBJ_GLOBAL, 0),
+            value,
+        )
+    }
+
+    fn get_ranged(
+        &self,
+        selector: TypedSelector<Vec<AudioStreamRangedDescription>>,
+    ) -> Result<Vec<RangedStreamFormat>, Error> {
+        let formats = self.get(selector)?;
+
+        Ok(formats.into_iter().map(RangedStreamFormat::from).collect())
+    }
+}
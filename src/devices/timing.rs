@@ -0,0 +1,192 @@
+use super::AudioDevice;
+use crate::{
+    aopa::AudioObjPropAddress,
+    error::Error,
+    events::listener::PropertyListener,
+    internals,
+    mscope::PropertyScope,
+    property::{
+        AudioValueRange, AVAILABLE_NOMINAL_SAMPLE_RATES, BUFFER_FRAME_SIZE,
+        BUFFER_FRAME_SIZE_RANGE, LATENCY, NOMINAL_SAMPLE_RATE, SAFETY_OFFSET,
+    },
+};
+use std::{ops::RangeInclusive, sync::mpsc, time::Duration};
+
+const SAMPLE_RATE: AudioObjPropAddress = NOMINAL_SAMPLE_RATE.address(PropertyScope::OBJ_GLOBAL, 0);
+const FRAME_SIZE: AudioObjPropAddress = BUFFER_FRAME_SIZE.address(PropertyScope::OBJ_GLOBAL, 0);
+
+/// A latency expressed both in frames and in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Latency {
+    pub frames: u32,
+    pub millis: f64,
+}
+
+impl Latency {
+    pub fn from_frames(frames: u32, sample_rate: f64) -> Self {
+        Self {
+            frames,
+            millis: f64::from(frames) * 1000.0 / sample_rate,
+        }
+    }
+}
+
+/// The latency components of one direction of a device, at `sample_rate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyReport {
+    pub scope: PropertyScope,
+    pub sample_rate: f64,
+    /// Latency of the device itself, e.g. its converters.
+    pub device: Latency,
+    /// How far ahead of (output) or behind (input) the hardware the HAL
+    /// transfers data.
+    pub safety_offset: Latency,
+    pub buffer: Latency,
+    /// Additional latency of each stream in `scope`.
+    pub streams: Vec<Latency>,
+}
+
+impl LatencyReport {
+    /// Sum of all components, counting the slowest stream. Saturates at
+    /// `u32::MAX` frames.
+    pub fn total(&self) -> Latency {
+        let stream = self.streams.iter().map(|stream| stream.frames).max();
+        // The frame counts come from the HAL, so don't trust them not to overflow.
+        let frames = [
+            self.safety_offset.frames,
+            self.buffer.frames,
+            stream.unwrap_or(0),
+        ]
+        .into_iter()
+        .fold(self.device.frames, u32::saturating_add);
+
+        Latency::from_frames(frames, self.sample_rate)
+    }
+}
+
+impl AudioDevice {
+    pub fn nominal_sample_rate(&self) -> Result<f64, Error> {
+        internals::get_property(self.device_id, SAMPLE_RATE)
+    }
+
+    /// Sample rates the device can be clocked at. Devices that support a
+    /// continuous range report it as a single range with distinct bounds.
+    pub fn available_nominal_sample_rates(&self) -> Result<Vec<AudioValueRange>, Error> {
+        internals::get_property(
+            self.device_id,
+            AVAILABLE_NOMINAL_SAMPLE_RATES.address(PropertyScope::OBJ_GLOBAL, 0),
+        )
+    }
+
+    /// Requests a new nominal sample rate.
+    ///
+    /// The HAL applies the change asynchronously, so the device may still run at
+    /// the old rate when this returns. Use
+    /// [`set_nominal_sample_rate_blocking`](Self::set_nominal_sample_rate_blocking)
+    /// to wait for the switch.
+    pub fn set_nominal_sample_rate(&self, rate: f64) -> Result<(), Error> {
+        self.check_sample_rate(rate)?;
+
+        internals::set_property(self.device_id, SAMPLE_RATE, &rate)
+    }
+
+    /// Sets the nominal sample rate and waits until the device reports it,
+    /// failing with [`Error::SampleRateTimeout`] if it doesn't within `timeout`.
+    pub fn set_nominal_sample_rate_blocking(
+        &self,
+        rate: f64,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        self.check_sample_rate(rate)?;
+
+        if self.nominal_sample_rate()? == rate {
+            return Ok(());
+        }
+
+        let id = self.device_id;
+        let (switched, wait) = mpsc::channel();
+        let _listener = PropertyListener::new(id, SAMPLE_RATE, move |_, _| {
+            if internals::get_property::<f64>(id, SAMPLE_RATE).is_ok_and(|current| current == rate)
+            {
+                let _ = switched.send(());
+            }
+        })?;
+
+        internals::set_property(id, SAMPLE_RATE, &rate)?;
+
+        wait.recv_timeout(timeout)
+            .map_err(|_| Error::SampleRateTimeout(rate))
+    }
+
+    /// Number of frames the device transfers per I/O cycle.
+    pub fn buffer_frame_size(&self) -> Result<u32, Error> {
+        internals::get_property(self.device_id, FRAME_SIZE)
+    }
+
+    /// Smallest and largest buffer frame size the device supports.
+    pub fn buffer_frame_size_range(&self) -> Result<RangeInclusive<u32>, Error> {
+        let range: AudioValueRange = internals::get_property(
+            self.device_id,
+            BUFFER_FRAME_SIZE_RANGE.address(PropertyScope::OBJ_GLOBAL, 0),
+        )?;
+
+        // The HAL reports whole frame counts as `Float64`s.
+        Ok(range.mMinimum as u32..=range.mMaximum as u32)
+    }
+
+    pub fn set_buffer_frame_size(&self, size: u32) -> Result<(), Error> {
+        let range = self.buffer_frame_size_range()?;
+
+        if !range.contains(&size) {
+            return Err(Error::BufferFrameSizeOutOfRange {
+                size,
+                min: *range.start(),
+                max: *range.end(),
+            });
+        }
+
+        internals::set_property(self.device_id, FRAME_SIZE, &size)
+    }
+
+    /// Collects the latency components of the input or output side of the device.
+    pub fn latency_report(&self, scope: PropertyScope) -> Result<LatencyReport, Error> {
+        if !matches!(scope, PropertyScope::DEV_INPUT | PropertyScope::DEV_OUTPUT) {
+            return Err(Error::UnexpectedParam);
+        }
+
+        let sample_rate = self.nominal_sample_rate()?;
+        let frames = |frames| Latency::from_frames(frames, sample_rate);
+
+        let device = internals::get_property(self.device_id, LATENCY.address(scope, 0))?;
+        let safety_offset =
+            internals::get_property(self.device_id, SAFETY_OFFSET.address(scope, 0))?;
+        let buffer = self.buffer_frame_size()?;
+        let streams = self
+            .streams(scope)?
+            .iter()
+            .map(|stream| stream.latency().map(frames))
+            .collect::<Result<_, Error>>()?;
+
+        Ok(LatencyReport {
+            scope,
+            sample_rate,
+            device: frames(device),
+            safety_offset: frames(safety_offset),
+            buffer: frames(buffer),
+            streams,
+        })
+    }
+
+    fn check_sample_rate(&self, rate: f64) -> Result<(), Error> {
+        let supported = self
+            .available_nominal_sample_rates()?
+            .iter()
+            .any(|range| (range.mMinimum..=range.mMaximum).contains(&rate));
+
+        if !supported {
+            return Err(Error::UnsupportedSampleRate(rate));
+        }
+
+        Ok(())
+    }
+}
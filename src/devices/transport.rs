@@ -0,0 +1,129 @@
+use super::AudioDevice;
+use crate::{
+    error::Error,
+    internals,
+    mscope::PropertyScope,
+    property::{CAN_BE_DEFAULT, IS_ALIVE, IS_HIDDEN, IS_RUNNING, TRANSPORT_TYPE},
+    sys::{
+        kAudioDeviceTransportTypeAVB, kAudioDeviceTransportTypeAggregate,
+        kAudioDeviceTransportTypeAirPlay, kAudioDeviceTransportTypeAutoAggregate,
+        kAudioDeviceTransportTypeBluetooth, kAudioDeviceTransportTypeBluetoothLE,
+        kAudioDeviceTransportTypeBuiltIn, kAudioDeviceTransportTypeDisplayPort,
+        kAudioDeviceTransportTypeFireWire, kAudioDeviceTransportTypeHDMI,
+        kAudioDeviceTransportTypePCI, kAudioDeviceTransportTypeThunderbolt,
+        kAudioDeviceTransportTypeUSB, kAudioDeviceTransportTypeUnknown,
+        kAudioDeviceTransportTypeVirtual,
+    },
+};
+
+/// How a device is connected to the system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransportType {
+    Unknown,
+    BuiltIn,
+    Aggregate,
+    /// An aggregate the system created on its own, e.g. for multi-output setups.
+    AutoAggregate,
+    Virtual,
+    Pci,
+    Usb,
+    FireWire,
+    Bluetooth,
+    BluetoothLE,
+    Hdmi,
+    DisplayPort,
+    AirPlay,
+    Avb,
+    Thunderbolt,
+    Other(u32),
+}
+
+impl TransportType {
+    const KNOWN: [Self; 15] = [
+        Self::Unknown,
+        Self::BuiltIn,
+        Self::Aggregate,
+        Self::AutoAggregate,
+        Self::Virtual,
+        Self::Pci,
+        Self::Usb,
+        Self::FireWire,
+        Self::Bluetooth,
+        Self::BluetoothLE,
+        Self::Hdmi,
+        Self::DisplayPort,
+        Self::AirPlay,
+        Self::Avb,
+        Self::Thunderbolt,
+    ];
+
+    pub fn from_raw(value: u32) -> Self {
+        Self::KNOWN
+            .into_iter()
+            .find(|kind| kind.raw() == value)
+            .unwrap_or(Self::Other(value))
+    }
+
+    pub const fn raw(self) -> u32 {
+        match self {
+            Self::Unknown => kAudioDeviceTransportTypeUnknown,
+            Self::BuiltIn => kAudioDeviceTransportTypeBuiltIn,
+            Self::Aggregate => kAudioDeviceTransportTypeAggregate,
+            Self::AutoAggregate => kAudioDeviceTransportTypeAutoAggregate,
+            Self::Virtual => kAudioDeviceTransportTypeVirtual,
+            Self::Pci => kAudioDeviceTransportTypePCI,
+            Self::Usb => kAudioDeviceTransportTypeUSB,
+            Self::FireWire => kAudioDeviceTransportTypeFireWire,
+            Self::Bluetooth => kAudioDeviceTransportTypeBluetooth,
+            Self::BluetoothLE => kAudioDeviceTransportTypeBluetoothLE,
+            Self::Hdmi => kAudioDeviceTransportTypeHDMI,
+            Self::DisplayPort => kAudioDeviceTransportTypeDisplayPort,
+            Self::AirPlay => kAudioDeviceTransportTypeAirPlay,
+            Self::Avb => kAudioDeviceTransportTypeAVB,
+            Self::Thunderbolt => kAudioDeviceTransportTypeThunderbolt,
+            Self::Other(value) => value,
+        }
+    }
+
+    pub const fn is_aggregate(self) -> bool {
+        matches!(self, Self::Aggregate | Self::AutoAggregate)
+    }
+
+    pub const fn is_wireless(self) -> bool {
+        matches!(self, Self::Bluetooth | Self::BluetoothLE | Self::AirPlay)
+    }
+}
+
+impl From<u32> for TransportType {
+    fn from(value: u32) -> Self {
+        Self::from_raw(value)
+    }
+}
+
+impl AudioDevice {
+    pub fn transport_type(&self) -> Result<TransportType, Error> {
+        self.get_global(TRANSPORT_TYPE).map(TransportType::from_raw)
+    }
+
+    /// Hidden devices are not meant to be shown to users, e.g. the private
+    /// sub-devices of an aggregate.
+    pub fn is_hidden(&self) -> Result<bool, Error> {
+        self.get_global(IS_HIDDEN)
+    }
+
+    /// Whether the device is still usable. Turns false right before a device
+    /// disappears.
+    pub fn is_alive(&self) -> Result<bool, Error> {
+        self.get_global(IS_ALIVE)
+    }
+
+    /// Whether the device is currently doing I/O for any process.
+    pub fn is_running(&self) -> Result<bool, Error> {
+        self.get_global(IS_RUNNING)
+    }
+
+    /// Whether the device can be picked as the default device in `scope`.
+    pub fn can_be_default(&self, scope: PropertyScope) -> Result<bool, Error> {
+        internals::get_property(self.device_id, CAN_BE_DEFAULT.address(scope, 0))
+    }
+}
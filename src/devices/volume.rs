@@ -0,0 +1,450 @@
+use super::AudioDevice;
+use crate::{
+    aopa::AudioObjPropAddress,
+    error::Error,
+    internals,
+    mscope::PropertyScope,
+    mselector::PropertySelector,
+    property::{
+        AudioValueRange, MUTE, VOLUME_DECIBELS, VOLUME_DECIBELS_TO_SCALAR, VOLUME_RANGE_DECIBELS,
+        VOLUME_SCALAR, VOLUME_SCALAR_TO_DECIBELS,
+    },
+};
+use std::{iter, ops::RangeInclusive};
+
+/// How [`ScopedDevice::set_volume_with`](super::ScopedDevice::set_volume_with)
+/// treats channels that currently differ in volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VolumeMode {
+    /// Every channel is set to the new volume.
+    Uniform,
+    /// The loudest channel is set to the new volume and the others are scaled
+    /// along with it, keeping the balance between them.
+    Proportional,
+}
+
+/// Which elements of a scope have a volume control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VolumeControlKind {
+    None,
+    /// Only the master element, which applies to every channel.
+    MasterOnly,
+    PerChannel,
+    /// The master element and the channels, applied on top of each other.
+    /// The overall volume is the master times the channel volumes; setting it
+    /// turns the master all the way up and writes the channels.
+    Both,
+}
+
+/// The volume and mute controls of a device in one scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeControl {
+    /// Whether the master element has a volume control.
+    pub master: bool,
+    /// Channels with their own volume control.
+    pub channels: Vec<u32>,
+    /// Whether the volume can be changed, as opposed to only read.
+    pub settable: bool,
+    /// Elements with a mute control, master first.
+    pub mute: Vec<u32>,
+    pub mute_settable: bool,
+}
+
+impl VolumeControl {
+    pub fn kind(&self) -> VolumeControlKind {
+        match (self.master, self.channels.is_empty()) {
+            (false, true) => VolumeControlKind::None,
+            (true, true) => VolumeControlKind::MasterOnly,
+            (false, false) => VolumeControlKind::PerChannel,
+            (true, false) => VolumeControlKind::Both,
+        }
+    }
+
+    /// Elements the volume is read from and written to: the channels if they
+    /// have their own controls, the master element otherwise. With
+    /// [`VolumeControlKind::Both`] the master is applied on top of these.
+    pub fn volume_elements(&self) -> Vec<u32> {
+        match self.kind() {
+            VolumeControlKind::None => Vec::new(),
+            VolumeControlKind::MasterOnly => vec![0],
+            VolumeControlKind::PerChannel | VolumeControlKind::Both => self.channels.clone(),
+        }
+    }
+
+    fn writable(self) -> Result<Self, Error> {
+        match self.kind() {
+            VolumeControlKind::None => Err(Error::NoVolumeControl),
+            _ if !self.settable => Err(Error::VolumeNotSettable),
+            _ => Ok(self),
+        }
+    }
+}
+
+/// Converts a scalar volume to decibels for controls that can't translate
+/// values themselves. Like CoreAudio's linear transfer function, the scalar
+/// maps linearly onto `range`.
+pub fn scalar_to_decibels(scalar: f32, range: &RangeInclusive<f32>) -> f32 {
+    range.start() + scalar.clamp(0.0, 1.0) * (range.end() - range.start())
+}
+
+/// Inverse of [`scalar_to_decibels`]. Values outside `range` are clamped.
+pub fn decibels_to_scalar(db: f32, range: &RangeInclusive<f32>) -> f32 {
+    let span = range.end() - range.start();
+
+    if span <= 0.0 {
+        return 1.0;
+    }
+
+    ((db - range.start()) / span).clamp(0.0, 1.0)
+}
+
+fn check_volume(vol: f32) -> Result<(), Error> {
+    if !(0.0..=1.0).contains(&vol) {
+        return Err(Error::InvalidVolume(vol));
+    }
+
+    Ok(())
+}
+
+impl AudioDevice {
+    /// Lowest and highest volume of a channel's volume control, in decibels.
+    pub fn decibel_range(
+        &self,
+        scope: PropertyScope,
+        channel: u32,
+    ) -> Result<RangeInclusive<f32>, Error> {
+        let range: AudioValueRange = internals::get_property(
+            self.device_id,
+            VOLUME_RANGE_DECIBELS.address(scope, channel),
+        )?;
+
+        Ok(range.mMinimum as f32..=range.mMaximum as f32)
+    }
+
+    /// Converts a scalar volume of `channel` to decibels, using the device's
+    /// own curve if it has one and [`scalar_to_decibels`] otherwise.
+    pub fn volume_scalar_to_decibels(
+        &self,
+        scope: PropertyScope,
+        channel: u32,
+        scalar: f32,
+    ) -> Result<f32, Error> {
+        let address = VOLUME_SCALAR_TO_DECIBELS.address(scope, channel);
+
+        if internals::has_property(self.device_id, address) {
+            return internals::translate_property(self.device_id, address, scalar);
+        }
+
+        Ok(scalar_to_decibels(
+            scalar,
+            &self.decibel_range(scope, channel)?,
+        ))
+    }
+
+    /// Converts a volume of `channel` in decibels to a scalar, using the
+    /// device's own curve if it has one and [`decibels_to_scalar`] otherwise.
+    pub fn volume_decibels_to_scalar(
+        &self,
+        scope: PropertyScope,
+        channel: u32,
+        db: f32,
+    ) -> Result<f32, Error> {
+        let address = VOLUME_DECIBELS_TO_SCALAR.address(scope, channel);
+
+        if internals::has_property(self.device_id, address) {
+            return internals::translate_property(self.device_id, address, db);
+        }
+
+        Ok(decibels_to_scalar(db, &self.decibel_range(scope, channel)?))
+    }
+}
+
+/// Volume and mute of one scope, shared by the scoped devices. Everything
+/// goes through [`volume_control`](AudioDevice::volume_control) to pick the
+/// elements to use.
+impl AudioDevice {
+    /// Describes the volume and mute controls of the device in `scope`.
+    pub fn volume_control(&self, scope: PropertyScope) -> Result<VolumeControl, Error> {
+        let has = |selector, element| self.has_control(selector, scope, element);
+        let settable = |selector, element: Option<&u32>| match element {
+            Some(element) => internals::is_property_settable(
+                self.device_id,
+                AudioObjPropAddress::new_with_element(selector, scope, *element),
+            ),
+            None => Ok(false),
+        };
+
+        let master = has(PropertySelector::DEV_VOLUME_SCALAR, 0);
+        let channels: Vec<u32> = self
+            .channels(scope)
+            .iter()
+            .copied()
+            .filter(|ch| *ch != 0 && has(PropertySelector::DEV_VOLUME_SCALAR, *ch))
+            .collect();
+        let mute: Vec<u32> = iter::once(0)
+            .chain(self.channels(scope).iter().copied())
+            .filter(|element| has(PropertySelector::DEV_MUTE, *element))
+            .collect();
+
+        let volume_element = channels.first().or(master.then_some(&0));
+        let settable_volume = settable(PropertySelector::DEV_VOLUME_SCALAR, volume_element)?;
+        let mute_settable = settable(PropertySelector::DEV_MUTE, mute.first())?;
+
+        Ok(VolumeControl {
+            master,
+            channels,
+            settable: settable_volume,
+            mute,
+            mute_settable,
+        })
+    }
+
+    pub(crate) fn volume_for_channel_in(
+        &self,
+        scope: PropertyScope,
+        ch: u32,
+    ) -> Result<f32, Error> {
+        let element = self.volume_element(&self.volume_control(scope)?, scope, ch)?;
+
+        internals::get_property(self.device_id, VOLUME_SCALAR.address(scope, element))
+    }
+
+    pub(crate) fn volume_db_for_channel_in(
+        &self,
+        scope: PropertyScope,
+        ch: u32,
+    ) -> Result<f32, Error> {
+        let element = self.volume_element(&self.volume_control(scope)?, scope, ch)?;
+        let address = VOLUME_DECIBELS.address(scope, element);
+
+        if internals::has_property(self.device_id, address) {
+            return internals::get_property(self.device_id, address);
+        }
+
+        let scalar =
+            internals::get_property(self.device_id, VOLUME_SCALAR.address(scope, element))?;
+        self.volume_scalar_to_decibels(scope, element, scalar)
+    }
+
+    pub(crate) fn avg_volume_in(&self, scope: PropertyScope) -> Result<f32, Error> {
+        let control = self.volume_control(scope)?;
+        let elements = control.volume_elements();
+
+        if elements.is_empty() {
+            return Err(Error::NoVolumeControl);
+        }
+
+        let master = self.master_gain(&control, scope)?;
+        let volumes = elements
+            .into_iter()
+            .map(|element| {
+                internals::get_property(self.device_id, VOLUME_SCALAR.address(scope, element))
+            })
+            .collect::<Result<Vec<f32>, Error>>()?;
+
+        let avg = volumes.iter().sum::<f32>() / volumes.len() as f32;
+
+        Ok(master * avg)
+    }
+
+    /// Devices with only a master control apply it to every channel.
+    pub(crate) fn set_volume_for_channel_in(
+        &self,
+        scope: PropertyScope,
+        ch: u32,
+        vol: f32,
+    ) -> Result<(), Error> {
+        check_volume(vol)?;
+
+        let control = self.volume_control(scope)?.writable()?;
+        let element = self.volume_element(&control, scope, ch)?;
+
+        internals::set_property(self.device_id, VOLUME_SCALAR.address(scope, element), &vol)
+    }
+
+    pub(crate) fn set_volume_in(
+        &self,
+        scope: PropertyScope,
+        vol: f32,
+        mode: VolumeMode,
+    ) -> Result<(), Error> {
+        check_volume(vol)?;
+
+        let control = self.volume_control(scope)?.writable()?;
+        let elements = control.volume_elements();
+        let current = elements
+            .iter()
+            .map(|element| {
+                internals::get_property(self.device_id, VOLUME_SCALAR.address(scope, *element))
+            })
+            .collect::<Result<Vec<f32>, Error>>()?;
+        let loudest = current.iter().copied().fold(0.0, f32::max);
+
+        let volumes: Vec<f32> = match mode {
+            VolumeMode::Proportional if loudest > 0.0 => {
+                current.iter().map(|cur| cur / loudest * vol).collect()
+            }
+            _ => vec![vol; elements.len()],
+        };
+
+        for (element, vol) in elements.into_iter().zip(volumes) {
+            internals::set_property(self.device_id, VOLUME_SCALAR.address(scope, element), &vol)?;
+        }
+
+        self.release_master(&control, scope)
+    }
+
+    pub(crate) fn set_volume_db_in(&self, scope: PropertyScope, db: f32) -> Result<(), Error> {
+        let control = self.volume_control(scope)?.writable()?;
+        let elements = control.volume_elements();
+
+        for element in &elements {
+            let range = self.decibel_range(scope, *element)?;
+
+            if !range.contains(&db) {
+                return Err(Error::DecibelsOutOfRange {
+                    db,
+                    min: *range.start(),
+                    max: *range.end(),
+                });
+            }
+        }
+
+        for element in elements {
+            let address = VOLUME_DECIBELS.address(scope, element);
+
+            if internals::has_property(self.device_id, address) {
+                internals::set_property(self.device_id, address, &db)?;
+            } else {
+                let scalar = self.volume_decibels_to_scalar(scope, element, db)?;
+                internals::set_property(
+                    self.device_id,
+                    VOLUME_SCALAR.address(scope, element),
+                    &scalar,
+                )?;
+            }
+        }
+
+        self.release_master(&control, scope)
+    }
+
+    pub(crate) fn balance_in(&self, scope: PropertyScope) -> Result<f32, Error> {
+        let [left, right] = self.stereo_pair(scope)?;
+        let left = self.volume_for_channel_in(scope, left)?;
+        let right = self.volume_for_channel_in(scope, right)?;
+        let loudest = left.max(right);
+
+        if loudest <= 0.0 {
+            return Ok(0.0);
+        }
+
+        Ok((right - left) / loudest)
+    }
+
+    /// Shifts the pair around its current average, so that the sum of both
+    /// sides, and with it the overall loudness, stays the same. If that would
+    /// push the louder side past 1.0, it is capped there and the pair gets
+    /// quieter instead.
+    pub(crate) fn set_balance_in(&self, scope: PropertyScope, balance: f32) -> Result<(), Error> {
+        if !(-1.0..=1.0).contains(&balance) {
+            return Err(Error::InvalidBalance(balance));
+        }
+
+        let [left, right] = self.stereo_pair(scope)?;
+        let control = self.volume_control(scope)?.writable()?;
+
+        // A master-only control can't attenuate one side.
+        if !(control.channels.contains(&left) && control.channels.contains(&right)) {
+            return Err(Error::NoVolumeControl);
+        }
+
+        let average = (self.volume_for_channel_in(scope, left)?
+            + self.volume_for_channel_in(scope, right)?)
+            / 2.0;
+        let louder = (2.0 * average / (2.0 - balance.abs())).min(1.0);
+        let quieter = louder * (1.0 - balance.abs());
+        let (left_vol, right_vol) = if balance < 0.0 {
+            (louder, quieter)
+        } else {
+            (quieter, louder)
+        };
+
+        self.set_volume_for_channel_in(scope, left, left_vol)?;
+        self.set_volume_for_channel_in(scope, right, right_vol)
+    }
+
+    pub(crate) fn muted_in(&self, scope: PropertyScope) -> Result<bool, Error> {
+        internals::get_muted(self.device_id, scope, &self.volume_control(scope)?.mute)
+    }
+
+    pub(crate) fn set_mute_in(&self, scope: PropertyScope, mute: bool) -> Result<(), Error> {
+        let control = self.volume_control(scope)?;
+
+        if control.mute.is_empty() {
+            return Err(Error::NoMuteControl);
+        }
+        if !control.mute_settable {
+            return Err(Error::MuteNotSettable);
+        }
+
+        for element in control.mute {
+            internals::set_property(self.device_id, MUTE.address(scope, element), &mute)?;
+        }
+
+        Ok(())
+    }
+
+    /// The master volume applied on top of the channels, 1.0 unless the scope
+    /// has both kinds of control.
+    fn master_gain(&self, control: &VolumeControl, scope: PropertyScope) -> Result<f32, Error> {
+        if control.kind() != VolumeControlKind::Both {
+            return Ok(1.0);
+        }
+
+        internals::get_property(self.device_id, VOLUME_SCALAR.address(scope, 0))
+    }
+
+    /// Turns the master all the way up after the channels were written, so they
+    /// alone make up the volume.
+    fn release_master(&self, control: &VolumeControl, scope: PropertyScope) -> Result<(), Error> {
+        if control.kind() != VolumeControlKind::Both {
+            return Ok(());
+        }
+
+        internals::set_property(self.device_id, VOLUME_SCALAR.address(scope, 0), &1.0f32)
+    }
+
+    /// The element that controls the volume of channel `ch`.
+    fn volume_element(
+        &self,
+        control: &VolumeControl,
+        scope: PropertyScope,
+        ch: u32,
+    ) -> Result<u32, Error> {
+        if control.channels.contains(&ch) || (ch == 0 && control.master) {
+            Ok(ch)
+        } else if control.master && self.channels(scope).contains(&ch) {
+            Ok(0)
+        } else {
+            Err(Error::NoVolumeControl)
+        }
+    }
+
+    /// The preferred stereo channels, or the first two channels if the device
+    /// has no preference.
+    fn stereo_pair(&self, scope: PropertyScope) -> Result<[u32; 2], Error> {
+        let pair = match self.preferred_stereo_channels(scope) {
+            Ok(pair) => pair,
+            Err(_) => match self.channels(scope) {
+                [left, right, ..] => [*left, *right],
+                _ => return Err(Error::NoStereoPair),
+            },
+        };
+
+        if pair[0] == pair[1] {
+            return Err(Error::NoStereoPair);
+        }
+
+        Ok(pair)
+    }
+}
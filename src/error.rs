@@ -1,31 +1,255 @@
-use coreaudio_sys::{AudioDeviceID, OSStatus};
-use std::{ffi::FromBytesUntilNulError, num::TryFromIntError, str::Utf8Error};
+use crate::{
+    aopa::AudioObjPropAddress,
+    sys::{
+        kAudioDevicePermissionsError, kAudioDeviceUnsupportedFormatError,
+        kAudioHardwareBadDeviceError, kAudioHardwareBadObjectError,
+        kAudioHardwareBadPropertySizeError, kAudioHardwareBadStreamError,
+        kAudioHardwareIllegalOperationError, kAudioHardwareNotReadyError,
+        kAudioHardwareNotRunningError, kAudioHardwareUnknownPropertyError,
+        kAudioHardwareUnspecifiedError, kAudioHardwareUnsupportedOperationError, AudioDeviceID,
+        AudioObjectID, OSStatus,
+    },
+};
+use std::{
+    ffi::FromBytesUntilNulError,
+    fmt::{self, Display, Formatter},
+    num::TryFromIntError,
+    str::Utf8Error,
+};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("OS error code: {0}")]
-    Raw(OSStatus),
+    #[error(transparent)]
+    Hal(#[from] HalError),
     #[error("Failed to parse UTF-8 bytes: {0}")]
     Utf8Error(#[from] Utf8Error),
     #[error("Invalid scalar volume value: {0} ")]
     InvalidVolume(f32),
+    #[error("Sample rate {0} Hz is not supported by the device")]
+    UnsupportedSampleRate(f64),
+    #[error("Timed out waiting for the device to switch to {0} Hz")]
+    SampleRateTimeout(f64),
+    #[error("Buffer frame size {size} is outside the supported range {min}..={max}")]
+    BufferFrameSizeOutOfRange { size: u32, min: u32, max: u32 },
+    #[error("Device has no volume control")]
+    NoVolumeControl,
+    #[error("The volume control is read-only")]
+    VolumeNotSettable,
+    #[error("Device has no mute control")]
+    NoMuteControl,
+    #[error("The mute control is read-only")]
+    MuteNotSettable,
+    #[error("Invalid balance value: {0}")]
+    InvalidBalance(f32),
+    #[error("Device has no stereo pair of channels")]
+    NoStereoPair,
+    #[error("Volume of {db} dB is outside the supported range {min}..={max} dB")]
+    DecibelsOutOfRange { db: f32, min: f32, max: f32 },
+    #[error("Device has no channel {0}")]
+    InvalidChannel(u32),
+    #[error("Malformed AudioChannelLayout")]
+    InvalidChannelLayout,
     #[error("Failed to convert integer values: {0}")]
     IntConversion(#[from] TryFromIntError),
     #[error("Failed to create a C string")]
     CStringMake(#[from] FromBytesUntilNulError),
+    #[error("Failed to convert a CFString")]
+    CFStringConversion,
     #[error("Unexpected parameter")]
     UnexpectedParam,
     #[error("Device ID links to an input device")]
     NotOutput,
+    #[error("Device ID links to an output device")]
+    NotInput,
+    #[error("No connected device has the UID {0:?}")]
+    UnknownDeviceUid(String),
     #[error("Unable to determine device type of device {0}")]
     UnknownDeviceType(AudioDeviceID),
     #[error("Callback registration failed")]
     CallbackRegister,
+    #[error("Event callback panicked: {0}")]
+    CallbackPanicked(String),
+    #[error("Event stream fell behind and dropped {0} events")]
+    Lagged(u64),
+    #[error("Unknown property selector: {0}")]
+    UnknownSelector(u32),
+    #[error("Unknown property scope: {0}")]
+    UnknownScope(u32),
 }
 
-impl From<i32> for Error {
-    fn from(value: i32) -> Self {
-        Self::Raw(value)
+impl From<OSStatus> for Error {
+    fn from(value: OSStatus) -> Self {
+        Self::Hal(HalError::new(value))
     }
 }
+
+/// Decoded `kAudioHardware*Error` / `kAudioDevice*Error` status codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HalErrorKind {
+    NotRunning,
+    Unspecified,
+    UnknownProperty,
+    BadPropertySize,
+    IllegalOperation,
+    BadObject,
+    BadDevice,
+    BadStream,
+    UnsupportedOperation,
+    NotReady,
+    UnsupportedFormat,
+    Permissions,
+    Other(OSStatus),
+}
+
+impl HalErrorKind {
+    const KNOWN: [Self; 12] = [
+        Self::NotRunning,
+        Self::Unspecified,
+        Self::UnknownProperty,
+        Self::BadPropertySize,
+        Self::IllegalOperation,
+        Self::BadObject,
+        Self::BadDevice,
+        Self::BadStream,
+        Self::UnsupportedOperation,
+        Self::NotReady,
+        Self::UnsupportedFormat,
+        Self::Permissions,
+    ];
+
+    pub fn from_status(status: OSStatus) -> Self {
+        Self::KNOWN
+            .into_iter()
+            .find(|kind| kind.status() == status)
+            .unwrap_or(Self::Other(status))
+    }
+
+    pub const fn status(self) -> OSStatus {
+        (match self {
+            Self::NotRunning => kAudioHardwareNotRunningError,
+            Self::Unspecified => kAudioHardwareUnspecifiedError,
+            Self::UnknownProperty => kAudioHardwareUnknownPropertyError,
+            Self::BadPropertySize => kAudioHardwareBadPropertySizeError,
+            Self::IllegalOperation => kAudioHardwareIllegalOperationError,
+            Self::BadObject => kAudioHardwareBadObjectError,
+            Self::BadDevice => kAudioHardwareBadDeviceError,
+            Self::BadStream => kAudioHardwareBadStreamError,
+            Self::UnsupportedOperation => kAudioHardwareUnsupportedOperationError,
+            Self::NotReady => kAudioHardwareNotReadyError,
+            Self::UnsupportedFormat => kAudioDeviceUnsupportedFormatError,
+            Self::Permissions => kAudioDevicePermissionsError,
+            Self::Other(status) => return status,
+        }) as OSStatus
+    }
+
+    const fn description(self) -> &'static str {
+        match self {
+            Self::NotRunning => "hardware not running",
+            Self::Unspecified => "unspecified hardware error",
+            Self::UnknownProperty => "unknown property",
+            Self::BadPropertySize => "bad property size",
+            Self::IllegalOperation => "illegal operation",
+            Self::BadObject => "bad object",
+            Self::BadDevice => "bad device",
+            Self::BadStream => "bad stream",
+            Self::UnsupportedOperation => "unsupported operation",
+            Self::NotReady => "hardware not ready",
+            Self::UnsupportedFormat => "unsupported format",
+            Self::Permissions => "device is hogged by another process",
+            Self::Other(_) => "OS error",
+        }
+    }
+}
+
+impl Display for HalErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({})",
+            self.description(),
+            FourCharCode(self.status())
+        )
+    }
+}
+
+/// Renders a status as a quoted four-character code when it is one, e.g. `'who?'`,
+/// and as a plain number otherwise.
+struct FourCharCode(OSStatus);
+
+impl Display for FourCharCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bytes = self.0.to_be_bytes();
+
+        if bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+            let code: String = bytes.iter().map(|b| char::from(*b)).collect();
+            write!(f, "'{code}'")
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+/// A failed HAL call, with the object and property it was made for, when known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HalError {
+    kind: HalErrorKind,
+    object: Option<AudioObjectID>,
+    address: Option<AudioObjPropAddress>,
+}
+
+impl HalError {
+    pub fn new(status: OSStatus) -> Self {
+        Self {
+            kind: HalErrorKind::from_status(status),
+            object: None,
+            address: None,
+        }
+    }
+
+    pub fn for_property(
+        status: OSStatus,
+        object: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Self {
+        Self {
+            kind: HalErrorKind::from_status(status),
+            object: Some(object),
+            address: Some(address),
+        }
+    }
+
+    pub const fn kind(&self) -> HalErrorKind {
+        self.kind
+    }
+
+    pub const fn status(&self) -> OSStatus {
+        self.kind.status()
+    }
+
+    pub const fn object(&self) -> Option<AudioObjectID> {
+        self.object
+    }
+
+    pub const fn address(&self) -> Option<AudioObjPropAddress> {
+        self.address
+    }
+}
+
+impl Display for HalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        if let Some(address) = self.address {
+            write!(f, " for property {address}")?;
+        }
+
+        if let Some(object) = self.object {
+            write!(f, " on object {object}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for HalError {}
@@ -0,0 +1,147 @@
+use super::listener::{ErrorHandler, PropertyListener};
+use crate::{
+    aopa::AudioObjPropAddress,
+    devices::{get_all_device_ids, AudioDevice},
+    error::Error,
+    internals,
+    mscope::PropertyScope,
+    mselector::PropertySelector,
+    sys::{kAudioObjectSystemObject, AudioDeviceID},
+};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+#[derive(Debug)]
+pub enum DeviceListEvent {
+    DeviceAdded(AudioDevice),
+    /// A device disappeared, along with the last name it was known by. The
+    /// name is `None` if it could not be read while the device was present.
+    DeviceRemoved(AudioDeviceID, Option<String>),
+}
+
+pub type Callback = Box<dyn Fn(DeviceListEvent) + Send + Sync>;
+const ADDRESS: AudioObjPropAddress =
+    AudioObjPropAddress::new(PropertySelector::HW_ALL_DEVICES, PropertyScope::OBJ_GLOBAL);
+const NAME_ADDRESS: AudioObjPropAddress =
+    AudioObjPropAddress::new(PropertySelector::DEV_OBJECT_NAME, PropertyScope::OBJ_GLOBAL);
+
+type Reporter = Arc<dyn Fn(Error) + Send + Sync>;
+type KnownDevices = Mutex<BTreeMap<AudioDeviceID, KnownDevice>>;
+
+/// A device present in the last observed list, with a listener keeping its
+/// name current so that removals can report it.
+struct KnownDevice {
+    name: Option<String>,
+    _name_listener: Option<PropertyListener>,
+}
+
+/// Subscribes to devices being added to or removed from the system.
+///
+/// The device list is diffed against the previous one on every notification,
+/// so the callback runs once per added or removed device.
+pub fn register(callback: Callback) -> Result<PropertyListener, Error> {
+    register_with_error_handler(callback, Box::new(|_| ()))
+}
+
+/// Like [`register`], but reports HAL errors and callback panics to `on_error`.
+///
+/// Devices whose name or properties cannot be read are reported here too. A
+/// device that fails to load when it appears is retried on the next change of
+/// the device list.
+pub fn register_with_error_handler(
+    callback: Callback,
+    on_error: ErrorHandler,
+) -> Result<PropertyListener, Error> {
+    let report: Reporter = Arc::from(on_error);
+    let known = Arc::new(KnownDevices::default());
+
+    for id in get_all_device_ids()? {
+        let name = internals::get_device_name(id)
+            .map_err(|err| report(err))
+            .ok();
+        let device = track(id, name, &known, &report);
+
+        known.lock().unwrap().insert(id, device);
+    }
+
+    let reporter = report.clone();
+    let on_change = move |_, _: &[AudioObjPropAddress]| {
+        let current = get_all_device_ids()?;
+
+        let mut devices = known.lock().unwrap();
+        let gone = devices
+            .keys()
+            .copied()
+            .filter(|id| !current.contains(id))
+            .collect::<Vec<_>>();
+        let removed = gone
+            .into_iter()
+            .filter_map(|id| devices.remove_entry(&id))
+            .collect::<Vec<_>>();
+        let added = current
+            .iter()
+            .copied()
+            .filter(|id| !devices.contains_key(id))
+            .filter_map(|id| AudioDevice::from_id(id).map_err(|err| report(err)).ok())
+            .collect::<Vec<_>>();
+        drop(devices);
+
+        for device in &added {
+            let tracked = track(device.id(), Some(device.name().into()), &known, &report);
+            known.lock().unwrap().insert(device.id(), tracked);
+        }
+
+        for (id, device) in removed {
+            callback(DeviceListEvent::DeviceRemoved(id, device.name));
+        }
+
+        for device in added {
+            callback(DeviceListEvent::DeviceAdded(device));
+        }
+
+        Ok(())
+    };
+
+    PropertyListener::with_error_handler(kAudioObjectSystemObject, ADDRESS, on_change, move |err| {
+        reporter(err)
+    })
+}
+
+pub fn unregister(subscription: PropertyListener) -> Result<(), Error> {
+    subscription.unregister()
+}
+
+/// Starts following renames of `id`. The listener only holds a weak reference
+/// to `known`, so it never keeps the subscription's state alive on its own.
+fn track(
+    id: AudioDeviceID,
+    name: Option<String>,
+    known: &Arc<KnownDevices>,
+    report: &Reporter,
+) -> KnownDevice {
+    let known: Weak<KnownDevices> = Arc::downgrade(known);
+    let on_error = report.clone();
+    let on_rename = move |id, _: &[AudioObjPropAddress]| {
+        let name = internals::get_device_name(id)?;
+        let Some(known) = known.upgrade() else {
+            return Ok(());
+        };
+
+        if let Some(device) = known.lock().unwrap().get_mut(&id) {
+            device.name = Some(name);
+        }
+
+        Ok(())
+    };
+    let listener =
+        PropertyListener::with_error_handler(id, NAME_ADDRESS, on_rename, move |err| on_error(err))
+            .map_err(|err| report(err))
+            .ok();
+
+    KnownDevice {
+        name,
+        _name_listener: listener,
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{
+    aopa::AudioObjPropAddress,
+    error::Error,
+    hal::{self, HalBackend},
+    sys::{
+        kAudioHardwareNoError, kAudioHardwareUnspecifiedError, AudioObjectID,
+        AudioObjectPropertyAddress, OSStatus, UInt32,
+    },
+};
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    ffi::c_void,
+    panic::{catch_unwind, AssertUnwindSafe},
+    slice,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+type Callback = dyn Fn(AudioObjectID, &[AudioObjPropAddress]) -> Result<(), Error> + Send + Sync;
+pub type ErrorHandler = Box<dyn Fn(Error) + Send + Sync>;
+
+struct Handlers {
+    callback: Box<Callback>,
+    on_error: Option<ErrorHandler>,
+}
+
+/// Handlers of every live listener, keyed by the token given to the HAL as client
+/// data. A notification that races with unregistering either finds no entry and
+/// does nothing, or holds its own reference until the callback returns.
+static HANDLERS: Mutex<BTreeMap<usize, Arc<Handlers>>> = Mutex::new(BTreeMap::new());
+static NEXT_TOKEN: AtomicUsize = AtomicUsize::new(1);
+
+/// A subscription to changes of one property on one audio object.
+///
+/// The callback receives the object that changed and the addresses reported by
+/// the HAL. Addresses with selectors or scopes this crate doesn't model are left
+/// out. The listener is removed when this value is dropped.
+///
+/// Callbacks run inside the HAL's notification thread. Panics are caught there
+/// and, like errors returned by fallible callbacks, reported to the error handler
+/// given to [`PropertyListener::with_error_handler`], if any.
+#[must_use = "the listener is removed as soon as it is dropped"]
+pub struct PropertyListener {
+    backend: Arc<dyn HalBackend>,
+    object: AudioObjectID,
+    address: AudioObjPropAddress,
+    token: usize,
+    registered: bool,
+}
+
+impl PropertyListener {
+    pub fn new<F>(
+        object: AudioObjectID,
+        address: AudioObjPropAddress,
+        callback: F,
+    ) -> Result<Self, Error>
+    where
+        F: Fn(AudioObjectID, &[AudioObjPropAddress]) + Send + Sync + 'static,
+    {
+        let handlers = Handlers {
+            callback: Box::new(move |object, changed| {
+                callback(object, changed);
+                Ok(())
+            }),
+            on_error: None,
+        };
+
+        Self::register(object, address, handlers)
+    }
+
+    /// Like [`PropertyListener::new`], but with a fallible callback whose errors,
+    /// along with any panic, are passed to `on_error`.
+    pub fn with_error_handler<F, E>(
+        object: AudioObjectID,
+        address: AudioObjPropAddress,
+        callback: F,
+        on_error: E,
+    ) -> Result<Self, Error>
+    where
+        F: Fn(AudioObjectID, &[AudioObjPropAddress]) -> Result<(), Error> + Send + Sync + 'static,
+        E: Fn(Error) + Send + Sync + 'static,
+    {
+        let handlers = Handlers {
+            callback: Box::new(callback),
+            on_error: Some(Box::new(on_error)),
+        };
+
+        Self::register(object, address, handlers)
+    }
+
+    fn register(
+        object: AudioObjectID,
+        address: AudioObjPropAddress,
+        handlers: Handlers,
+    ) -> Result<Self, Error> {
+        let token = NEXT_TOKEN.fetch_add(1, Ordering::Relaxed);
+        HANDLERS.lock().unwrap().insert(token, Arc::new(handlers));
+
+        let mut listener = Self {
+            backend: hal::backend(),
+            object,
+            address,
+            token,
+            registered: false,
+        };
+
+        listener.backend.add_property_listener(
+            object,
+            address,
+            trampoline,
+            listener.client_data(),
+        )?;
+        listener.registered = true;
+
+        Ok(listener)
+    }
+
+    pub const fn object(&self) -> AudioObjectID {
+        self.object
+    }
+
+    pub const fn address(&self) -> AudioObjPropAddress {
+        self.address
+    }
+
+    /// Removes the listener, reporting any error from the HAL.
+    pub fn unregister(mut self) -> Result<(), Error> {
+        self.remove()
+    }
+
+    fn remove(&mut self) -> Result<(), Error> {
+        // Dropped outside the lock: the handlers may own listeners of their own.
+        let handlers = HANDLERS.lock().unwrap().remove(&self.token);
+        drop(handlers);
+
+        if !self.registered {
+            return Ok(());
+        }
+
+        self.registered = false;
+        self.backend.remove_property_listener(
+            self.object,
+            self.address,
+            trampoline,
+            self.client_data(),
+        )
+    }
+
+    fn client_data(&self) -> *mut c_void {
+        self.token as *mut c_void
+    }
+}
+
+impl Drop for PropertyListener {
+    fn drop(&mut self) {
+        let _ = self.remove();
+    }
+}
+
+impl std::fmt::Debug for PropertyListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PropertyListener")
+            .field("object", &self.object)
+            .field("address", &self.address)
+            .field("registered", &self.registered)
+            .finish_non_exhaustive()
+    }
+}
+
+unsafe extern "C" fn trampoline(
+    in_obj_id: AudioObjectID,
+    in_number_addresses: UInt32,
+    in_addresses: *const AudioObjectPropertyAddress,
+    in_client_data: *mut c_void,
+) -> OSStatus {
+    let token = in_client_data as usize;
+    let Some(handlers) = HANDLERS.lock().unwrap().get(&token).cloned() else {
+        return kAudioHardwareNoError as OSStatus;
+    };
+
+    let addresses = if in_addresses.is_null() {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(in_addresses, in_number_addresses as usize) }
+    };
+    let changed = addresses
+        .iter()
+        .filter_map(|address| AudioObjPropAddress::try_from(*address).ok())
+        .collect::<Vec<_>>();
+
+    let (error, status) = match catch_unwind(AssertUnwindSafe(|| {
+        (handlers.callback)(in_obj_id, &changed)
+    })) {
+        Ok(Ok(())) => return kAudioHardwareNoError as OSStatus,
+        Ok(Err(err)) => (err, kAudioHardwareNoError as OSStatus),
+        Err(panic) => (
+            Error::CallbackPanicked(panic_message(&*panic)),
+            kAudioHardwareUnspecifiedError as OSStatus,
+        ),
+    };
+
+    if let Some(on_error) = &handlers.on_error {
+        if catch_unwind(AssertUnwindSafe(|| on_error(error))).is_err() {
+            return kAudioHardwareUnspecifiedError as OSStatus;
+        }
+    }
+
+    status
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(msg) = panic.downcast_ref::<&str>() {
+        return (*msg).into();
+    }
+
+    panic
+        .downcast_ref::<String>()
+        .cloned()
+        .unwrap_or_else(|| "unknown panic payload".into())
+}
+
+/// A group of listeners that make up one logical subscription.
+///
+/// All listeners are removed when this value is dropped.
+#[must_use = "the subscription is removed as soon as it is dropped"]
+#[derive(Debug)]
+pub struct Subscription {
+    listeners: Vec<PropertyListener>,
+}
+
+impl Subscription {
+    pub(crate) fn new(listeners: Vec<PropertyListener>) -> Self {
+        Self { listeners }
+    }
+
+    /// Removes every listener, reporting the first error from the HAL.
+    pub fn unregister(self) -> Result<(), Error> {
+        self.listeners
+            .into_iter()
+            .try_for_each(PropertyListener::unregister)
+    }
+}
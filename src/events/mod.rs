@@ -1,2 +1,7 @@
+pub mod devices_changed;
 pub mod input_changed;
+pub mod listener;
 pub mod output_changed;
+#[cfg(feature = "futures")]
+pub mod stream;
+pub mod volume;
@@ -1,73 +1,44 @@
+use super::listener::{ErrorHandler, PropertyListener};
 use crate::{
     aopa::AudioObjPropAddress,
     devices::{AudioDevice, AudioOutputDevice},
     error::Error,
     mscope::PropertyScope,
     mselector::PropertySelector,
+    sys::kAudioObjectSystemObject,
 };
-use coreaudio_sys::{
-    kAudioObjectSystemObject, AudioObjectAddPropertyListener, AudioObjectID,
-    AudioObjectPropertyAddress, AudioObjectRemovePropertyListener, OSStatus, UInt32,
-};
-use std::{ffi::c_void, ptr::null_mut, sync::RwLock};
 
-type Callback = fn(AudioOutputDevice);
+pub type Callback = Box<dyn Fn(AudioOutputDevice) + Send + Sync>;
 const ADDRESS: AudioObjPropAddress = AudioObjPropAddress::new(
     PropertySelector::HW_DEFAULT_OUTPUT_DEV,
     PropertyScope::OBJ_GLOBAL,
 );
-static CALLBACK: RwLock<Option<Callback>> = RwLock::new(None);
-
-pub fn register(callback: Callback) -> Result<(), Error> {
-    let status = unsafe {
-        AudioObjectAddPropertyListener(
-            kAudioObjectSystemObject,
-            &ADDRESS.into(),
-            Some(callback_wrapper),
-            null_mut(),
-        )
-    };
-
-    if status != 0 {
-        return Err(status.into());
-    }
 
-    let mut slot = CALLBACK.write().unwrap();
-    slot.replace(callback);
-
-    Ok(())
+/// Subscribes to default output device changes.
+///
+/// Every call creates an independent subscription, which stays active until the
+/// returned listener is dropped or passed to [`unregister`]. Notifications for
+/// which the new default device can't be read are skipped.
+pub fn register(callback: Callback) -> Result<PropertyListener, Error> {
+    register_with_error_handler(callback, Box::new(|_| ()))
 }
 
-pub fn unregister() -> Result<(), Error> {
-    let status = unsafe {
-        AudioObjectRemovePropertyListener(
-            kAudioObjectSystemObject,
-            &ADDRESS.into(),
-            Some(callback_wrapper),
-            null_mut(),
-        )
-    };
-
-    if status != 0 {
-        return Err(status.into());
-    }
-
-    let mut slot = CALLBACK.write().unwrap();
-    slot.take();
-
-    Ok(())
+/// Like [`register`], but reports HAL errors and callback panics to `on_error`.
+pub fn register_with_error_handler(
+    callback: Callback,
+    on_error: ErrorHandler,
+) -> Result<PropertyListener, Error> {
+    PropertyListener::with_error_handler(
+        kAudioObjectSystemObject,
+        ADDRESS,
+        move |_, _| {
+            callback(AudioDevice::default_output()?);
+            Ok(())
+        },
+        on_error,
+    )
 }
 
-unsafe extern "C" fn callback_wrapper(
-    _in_obj_id: AudioObjectID,
-    _in_number_addresses: UInt32,
-    _in_addresses: *const AudioObjectPropertyAddress,
-    _in_client_data: *mut c_void,
-) -> OSStatus {
-    let device = AudioDevice::default_output().unwrap();
-    let hl_clbk = unsafe { CALLBACK.read().unwrap_unchecked().unwrap_unchecked() };
-
-    hl_clbk(device);
-
-    1
+pub fn unregister(subscription: PropertyListener) -> Result<(), Error> {
+    subscription.unregister()
 }
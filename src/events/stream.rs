@@ -0,0 +1,165 @@
+//! Event subscriptions as [`futures_core::Stream`]s.
+//!
+//! Each stream owns its listener and buffers at most `capacity` items. When the
+//! consumer falls behind and the buffer is full, the oldest buffered item is
+//! discarded to make room for the newest one. The next poll then yields
+//! `Err(Error::Lagged(n))` with the number of discarded items before resuming
+//! with the remaining buffered items. HAL errors that occur while producing an
+//! event are delivered in-band as `Err` items and count towards the buffer.
+
+use super::{
+    devices_changed::{self, DeviceListEvent},
+    input_changed,
+    listener::PropertyListener,
+    output_changed,
+};
+use crate::{
+    aopa::AudioObjPropAddress,
+    devices::{AudioInputDevice, AudioOutputDevice},
+    error::Error,
+    sys::AudioObjectID,
+};
+use futures_core::Stream;
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Debug)]
+struct Buffer<T> {
+    items: VecDeque<Result<T, Error>>,
+    dropped: u64,
+    waker: Option<Waker>,
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    capacity: usize,
+    buffer: Mutex<Buffer<T>>,
+}
+
+impl<T> Shared<T> {
+    fn push(&self, item: Result<T, Error>) {
+        let mut buffer = self.buffer.lock().unwrap();
+
+        if buffer.items.len() == self.capacity {
+            buffer.items.pop_front();
+            buffer.dropped += 1;
+        }
+
+        buffer.items.push_back(item);
+
+        if let Some(waker) = buffer.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A stream of events backed by a HAL listener, which is removed when the
+/// stream is dropped.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct EventStream<T> {
+    shared: Arc<Shared<T>>,
+    _listener: PropertyListener,
+}
+
+impl<T: Send + 'static> EventStream<T> {
+    fn new<F>(capacity: usize, subscribe: F) -> Result<Self, Error>
+    where
+        F: FnOnce(Arc<Shared<T>>) -> Result<PropertyListener, Error>,
+    {
+        let shared = Arc::new(Shared {
+            capacity: capacity.max(1),
+            buffer: Mutex::new(Buffer {
+                items: VecDeque::new(),
+                dropped: 0,
+                waker: None,
+            }),
+        });
+
+        let listener = subscribe(shared.clone())?;
+
+        Ok(Self {
+            shared,
+            _listener: listener,
+        })
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut buffer = self.shared.buffer.lock().unwrap();
+
+        if buffer.dropped > 0 {
+            let dropped = std::mem::take(&mut buffer.dropped);
+            return Poll::Ready(Some(Err(Error::Lagged(dropped))));
+        }
+
+        match buffer.items.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None => {
+                buffer.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+pub fn default_output_changes(capacity: usize) -> Result<EventStream<AudioOutputDevice>, Error> {
+    EventStream::new(capacity, |shared| {
+        let on_error = shared.clone();
+
+        output_changed::register_with_error_handler(
+            Box::new(move |device| shared.push(Ok(device))),
+            Box::new(move |err| on_error.push(Err(err))),
+        )
+    })
+}
+
+pub fn default_input_changes(capacity: usize) -> Result<EventStream<AudioInputDevice>, Error> {
+    EventStream::new(capacity, |shared| {
+        let on_error = shared.clone();
+
+        input_changed::register_with_error_handler(
+            Box::new(move |device| shared.push(Ok(device))),
+            Box::new(move |err| on_error.push(Err(err))),
+        )
+    })
+}
+
+pub fn device_list_changes(capacity: usize) -> Result<EventStream<DeviceListEvent>, Error> {
+    EventStream::new(capacity, |shared| {
+        let on_error = shared.clone();
+
+        devices_changed::register_with_error_handler(
+            Box::new(move |event| shared.push(Ok(event))),
+            Box::new(move |err| on_error.push(Err(err))),
+        )
+    })
+}
+
+/// Streams the addresses reported for every change of `address` on `object`.
+pub fn property_changes(
+    object: AudioObjectID,
+    address: AudioObjPropAddress,
+    capacity: usize,
+) -> Result<EventStream<Vec<AudioObjPropAddress>>, Error> {
+    EventStream::new(capacity, |shared| {
+        let on_error = shared.clone();
+
+        PropertyListener::with_error_handler(
+            object,
+            address,
+            move |_, changed| {
+                shared.push(Ok(changed.to_vec()));
+                Ok(())
+            },
+            move |err| on_error.push(Err(err)),
+        )
+    })
+}
@@ -0,0 +1,170 @@
+use super::listener::{PropertyListener, Subscription};
+use crate::{
+    aopa::AudioObjPropAddress, devices::AudioDevice, error::Error, internals,
+    mscope::PropertyScope, mselector::PropertySelector, sys::AudioDeviceID,
+};
+use std::sync::{Arc, Mutex};
+
+const MASTER_ELEMENT: u32 = 0;
+
+/// Per-channel volumes and the separately listened master volume.
+type Volumes = (Vec<(u32, f32)>, Option<f32>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeChanged {
+    pub device: AudioDeviceID,
+    pub scope: PropertyScope,
+    /// New scalar volume of every channel, as `(channel, volume)` pairs.
+    pub channels: Vec<(u32, f32)>,
+    pub average: f32,
+    /// Scalar volume of the master element, on devices that have one on top
+    /// of the per-channel controls.
+    pub master: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MuteChanged {
+    pub device: AudioDeviceID,
+    pub scope: PropertyScope,
+    pub muted: bool,
+}
+
+/// Subscribes to volume changes of `device` in `scope`.
+///
+/// The HAL notifies each channel separately, so a single user action usually
+/// produces several notifications. They are coalesced by comparing against the
+/// last seen volumes: the callback only runs when at least one channel or the
+/// master element differs.
+pub fn on_volume_changed<F>(
+    device: &AudioDevice,
+    scope: PropertyScope,
+    callback: F,
+) -> Result<Subscription, Error>
+where
+    F: Fn(VolumeChanged) + Send + Sync + 'static,
+{
+    let id = device.id();
+    let channels = device.volume_control(scope)?.volume_elements();
+
+    if channels.is_empty() {
+        return Err(Error::NoVolumeControl);
+    }
+
+    let master = (!channels.contains(&MASTER_ELEMENT)
+        && internals::has_property(id, volume_address(scope, MASTER_ELEMENT)))
+    .then_some(MASTER_ELEMENT);
+    let elements: Vec<u32> = channels.iter().copied().chain(master).collect();
+
+    let last = Arc::new(Mutex::new(read_volumes(id, scope, &channels, master).ok()));
+    let callback = Arc::new(callback);
+    let channels: Arc<[u32]> = channels.into();
+
+    let listeners = elements
+        .into_iter()
+        .map(|element| {
+            let last = last.clone();
+            let callback = callback.clone();
+            let channels = channels.clone();
+
+            PropertyListener::new(id, volume_address(scope, element), move |_, _| {
+                let Ok(current) = read_volumes(id, scope, &channels, master) else {
+                    return;
+                };
+
+                let mut last = last.lock().unwrap();
+                if last.as_ref() == Some(&current) {
+                    return;
+                }
+                last.replace(current.clone());
+                drop(last);
+
+                let (channels, master) = current;
+                let average =
+                    channels.iter().map(|(_, vol)| vol).sum::<f32>() / channels.len() as f32;
+                callback(VolumeChanged {
+                    device: id,
+                    scope,
+                    channels,
+                    average,
+                    master,
+                });
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(Subscription::new(listeners))
+}
+
+/// Subscribes to mute state transitions of `device` in `scope`.
+pub fn on_mute_changed<F>(
+    device: &AudioDevice,
+    scope: PropertyScope,
+    callback: F,
+) -> Result<Subscription, Error>
+where
+    F: Fn(MuteChanged) + Send + Sync + 'static,
+{
+    let id = device.id();
+    let elements: Arc<[u32]> = device.volume_control(scope)?.mute.into();
+
+    if elements.is_empty() {
+        return Err(Error::NoMuteControl);
+    }
+
+    let last = Arc::new(Mutex::new(internals::get_muted(id, scope, &elements).ok()));
+    let callback = Arc::new(callback);
+
+    let listeners = elements
+        .iter()
+        .map(|element| {
+            let last = last.clone();
+            let callback = callback.clone();
+            let elements = elements.clone();
+
+            PropertyListener::new(id, mute_address(scope, *element), move |_, _| {
+                let Ok(muted) = internals::get_muted(id, scope, &elements) else {
+                    return;
+                };
+
+                if last.lock().unwrap().replace(muted) == Some(muted) {
+                    return;
+                }
+
+                callback(MuteChanged {
+                    device: id,
+                    scope,
+                    muted,
+                });
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(Subscription::new(listeners))
+}
+
+fn volume_address(scope: PropertyScope, element: u32) -> AudioObjPropAddress {
+    AudioObjPropAddress::new_with_element(PropertySelector::DEV_VOLUME_SCALAR, scope, element)
+}
+
+fn mute_address(scope: PropertyScope, element: u32) -> AudioObjPropAddress {
+    AudioObjPropAddress::new_with_element(PropertySelector::DEV_MUTE, scope, element)
+}
+
+/// Volumes of `channels`, and of the `master` element if it is listened to
+/// separately.
+fn read_volumes(
+    id: AudioDeviceID,
+    scope: PropertyScope,
+    channels: &[u32],
+    master: Option<u32>,
+) -> Result<Volumes, Error> {
+    let channels = channels
+        .iter()
+        .map(|ch| internals::get_property(id, volume_address(scope, *ch)).map(|vol| (*ch, vol)))
+        .collect::<Result<_, Error>>()?;
+    let master = master
+        .map(|element| internals::get_property(id, volume_address(scope, element)))
+        .transpose()?;
+
+    Ok((channels, master))
+}
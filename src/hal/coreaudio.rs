@@ -0,0 +1,281 @@
+use super::{HalBackend, ListenerProc};
+use crate::{
+    aopa::AudioObjPropAddress,
+    error::{Error, HalError},
+    sys::{
+        kAudioHardwareBadPropertySizeError, kCFStringEncodingUTF8, AudioObjectAddPropertyListener,
+        AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize, AudioObjectHasProperty,
+        AudioObjectID, AudioObjectIsPropertySettable, AudioObjectRemovePropertyListener,
+        AudioObjectSetPropertyData, Boolean, CFIndex, CFRelease, CFStringCreateWithBytes,
+        CFStringGetCString, CFStringGetLength, CFStringGetMaximumSizeForEncoding, CFStringRef,
+        OSStatus,
+    },
+};
+use std::{
+    ffi::{c_char, c_void, CStr},
+    mem::size_of,
+    ptr::{self, null},
+};
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {}
+
+/// An owned `CFStringRef`, released on drop.
+struct CfString(CFStringRef);
+
+impl CfString {
+    fn new(value: &str) -> Result<Self, Error> {
+        let string = unsafe {
+            CFStringCreateWithBytes(
+                null(),
+                value.as_ptr(),
+                CFIndex::try_from(value.len())?,
+                kCFStringEncodingUTF8,
+                0,
+            )
+        };
+
+        if string.is_null() {
+            return Err(Error::CFStringConversion);
+        }
+
+        Ok(Self(string))
+    }
+
+    fn to_string(&self) -> Result<String, Error> {
+        let len = unsafe { CFStringGetLength(self.0) };
+        let capacity = unsafe { CFStringGetMaximumSizeForEncoding(len, kCFStringEncodingUTF8) } + 1;
+        let mut buf = vec![0u8; usize::try_from(capacity)?];
+
+        let ok = unsafe {
+            CFStringGetCString(
+                self.0,
+                buf.as_mut_ptr().cast::<c_char>(),
+                capacity,
+                kCFStringEncodingUTF8,
+            )
+        };
+
+        if ok == 0 {
+            return Err(Error::CFStringConversion);
+        }
+
+        Ok(CStr::from_bytes_until_nul(&buf)?.to_str()?.into())
+    }
+}
+
+impl Drop for CfString {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.0.cast()) };
+    }
+}
+
+/// Backend that forwards every call to the system CoreAudio HAL.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CoreAudioHal;
+
+impl HalBackend for CoreAudioHal {
+    fn has_property(&self, id: AudioObjectID, address: AudioObjPropAddress) -> bool {
+        let ret = unsafe { AudioObjectHasProperty(id, &address.into()) };
+        ret != 0
+    }
+
+    fn is_property_settable(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<bool, Error> {
+        let mut settable: Boolean = 0;
+
+        let status = unsafe { AudioObjectIsPropertySettable(id, &address.into(), &mut settable) };
+
+        if status != 0 {
+            return Err(HalError::for_property(status, id, address).into());
+        }
+
+        Ok(settable != 0)
+    }
+
+    fn get_property_data_size(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<usize, Error> {
+        let mut size = 0u32;
+
+        let status =
+            unsafe { AudioObjectGetPropertyDataSize(id, &address.into(), 0, null(), &mut size) };
+
+        if status != 0 {
+            return Err(HalError::for_property(status, id, address).into());
+        }
+
+        Ok(usize::try_from(size)?)
+    }
+
+    fn get_property_data(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        data: &mut [u8],
+    ) -> Result<usize, Error> {
+        let ptr = data.as_mut_ptr().cast::<c_void>();
+        let mut data_size = u32::try_from(data.len())?;
+
+        let status = unsafe {
+            AudioObjectGetPropertyData(id, &address.into(), 0, null(), &mut data_size, ptr)
+        };
+
+        if status != 0 {
+            return Err(HalError::for_property(status, id, address).into());
+        }
+
+        Ok(usize::try_from(data_size)?)
+    }
+
+    fn set_property_data(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let ptr = data.as_ptr().cast::<c_void>();
+        let data_size = u32::try_from(data.len())?;
+
+        let status =
+            unsafe { AudioObjectSetPropertyData(id, &address.into(), 0, null(), data_size, ptr) };
+
+        if status != 0 {
+            return Err(HalError::for_property(status, id, address).into());
+        }
+
+        Ok(())
+    }
+
+    fn get_property_data_with_string_qualifier(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        qualifier: &str,
+        data: &mut [u8],
+    ) -> Result<usize, Error> {
+        let qualifier = CfString::new(qualifier)?;
+        let ptr = data.as_mut_ptr().cast::<c_void>();
+        let mut data_size = u32::try_from(data.len())?;
+
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                id,
+                &address.into(),
+                size_of::<CFStringRef>() as u32,
+                ptr::from_ref(&qualifier.0).cast(),
+                &mut data_size,
+                ptr,
+            )
+        };
+
+        if status != 0 {
+            return Err(HalError::for_property(status, id, address).into());
+        }
+
+        Ok(usize::try_from(data_size)?)
+    }
+
+    fn get_string_property(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<String, Error> {
+        let mut string: CFStringRef = null();
+        let mut data_size = size_of::<CFStringRef>() as u32;
+
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                id,
+                &address.into(),
+                0,
+                null(),
+                &mut data_size,
+                ptr::from_mut(&mut string).cast(),
+            )
+        };
+
+        if status != 0 {
+            return Err(HalError::for_property(status, id, address).into());
+        }
+
+        if string.is_null() || data_size as usize != size_of::<CFStringRef>() {
+            return Err(HalError::for_property(
+                kAudioHardwareBadPropertySizeError as OSStatus,
+                id,
+                address,
+            )
+            .into());
+        }
+
+        // The HAL hands out a retained reference that the caller must release.
+        CfString(string).to_string()
+    }
+
+    fn set_string_property(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        value: &str,
+    ) -> Result<(), Error> {
+        let string = CfString::new(value)?;
+
+        let status = unsafe {
+            AudioObjectSetPropertyData(
+                id,
+                &address.into(),
+                0,
+                null(),
+                size_of::<CFStringRef>() as u32,
+                ptr::from_ref(&string.0).cast(),
+            )
+        };
+
+        if status != 0 {
+            return Err(HalError::for_property(status, id, address).into());
+        }
+
+        Ok(())
+    }
+
+    fn add_property_listener(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        listener: ListenerProc,
+        client_data: *mut c_void,
+    ) -> Result<(), Error> {
+        let status = unsafe {
+            AudioObjectAddPropertyListener(id, &address.into(), Some(listener), client_data)
+        };
+
+        if status != 0 {
+            return Err(HalError::for_property(status, id, address).into());
+        }
+
+        Ok(())
+    }
+
+    fn remove_property_listener(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        listener: ListenerProc,
+        client_data: *mut c_void,
+    ) -> Result<(), Error> {
+        let status = unsafe {
+            AudioObjectRemovePropertyListener(id, &address.into(), Some(listener), client_data)
+        };
+
+        if status != 0 {
+            return Err(HalError::for_property(status, id, address).into());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Pluggable access to the audio hardware abstraction layer.
+//!
+//! Everything in this crate talks to the HAL through a [`HalBackend`]. On macOS
+//! the default backend is [`CoreAudioHal`], which forwards to the real CoreAudio
+//! API. On other platforms the default is an empty [`SimulatedHal`], and tests
+//! can install a scripted one with [`scoped_backend`].
+
+use crate::{aopa::AudioObjPropAddress, error::Error, sys::AudioObjectID};
+use std::{
+    cell::RefCell,
+    ffi::c_void,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+pub use crate::sys::AudioObjectPropertyAddress;
+#[cfg(target_os = "macos")]
+pub use coreaudio::CoreAudioHal;
+pub use simulated::{ReadFault, SimDevice, SimulatedHal};
+
+#[cfg(target_os = "macos")]
+mod coreaudio;
+mod simulated;
+
+/// Signature of a HAL property listener, identical to CoreAudio's
+/// `AudioObjectPropertyListenerProc`.
+pub type ListenerProc = unsafe extern "C" fn(
+    AudioObjectID,
+    u32,
+    *const AudioObjectPropertyAddress,
+    *mut c_void,
+) -> crate::sys::OSStatus;
+
+pub trait HalBackend: Send + Sync {
+    fn has_property(&self, id: AudioObjectID, address: AudioObjPropAddress) -> bool;
+
+    /// Whether the property can be written, as opposed to only read.
+    fn is_property_settable(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<bool, Error>;
+
+    fn get_property_data_size(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<usize, Error>;
+
+    /// Reads the property into `data` and returns the number of bytes written.
+    fn get_property_data(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        data: &mut [u8],
+    ) -> Result<usize, Error>;
+
+    fn set_property_data(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        data: &[u8],
+    ) -> Result<(), Error>;
+
+    /// Like [`get_property_data`](Self::get_property_data), but passes a
+    /// `CFString` qualifier, as needed by translation properties such as
+    /// `kAudioHardwarePropertyTranslateUIDToDevice`.
+    fn get_property_data_with_string_qualifier(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        qualifier: &str,
+        data: &mut [u8],
+    ) -> Result<usize, Error>;
+
+    /// Reads a `CFString` property.
+    fn get_string_property(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<String, Error>;
+
+    /// Writes a `CFString` property.
+    fn set_string_property(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        value: &str,
+    ) -> Result<(), Error>;
+
+    fn add_property_listener(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        listener: ListenerProc,
+        client_data: *mut c_void,
+    ) -> Result<(), Error>;
+
+    fn remove_property_listener(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        listener: ListenerProc,
+        client_data: *mut c_void,
+    ) -> Result<(), Error>;
+}
+
+static GLOBAL_BACKEND: RwLock<Option<Arc<dyn HalBackend>>> = RwLock::new(None);
+static PLATFORM_BACKEND: OnceLock<Arc<dyn HalBackend>> = OnceLock::new();
+
+thread_local! {
+    static THREAD_BACKEND: RefCell<Option<Arc<dyn HalBackend>>> = const { RefCell::new(None) };
+}
+
+/// Returns the backend used by the current thread.
+///
+/// A backend installed with [`scoped_backend`] takes precedence over one set
+/// with [`set_backend`], which in turn takes precedence over the platform default.
+pub fn backend() -> Arc<dyn HalBackend> {
+    if let Some(backend) = THREAD_BACKEND.with(|slot| slot.borrow().clone()) {
+        return backend;
+    }
+
+    if let Some(backend) = GLOBAL_BACKEND.read().unwrap().as_ref() {
+        return backend.clone();
+    }
+
+    PLATFORM_BACKEND.get_or_init(platform_backend).clone()
+}
+
+/// Replaces the process-wide backend.
+pub fn set_backend(backend: Arc<dyn HalBackend>) {
+    GLOBAL_BACKEND.write().unwrap().replace(backend);
+}
+
+/// Installs `backend` for the current thread until the returned guard is dropped.
+pub fn scoped_backend(backend: Arc<dyn HalBackend>) -> ScopedBackend {
+    let previous = THREAD_BACKEND.with(|slot| slot.borrow_mut().replace(backend));
+
+    ScopedBackend { previous }
+}
+
+#[must_use = "the backend is uninstalled as soon as the guard is dropped"]
+pub struct ScopedBackend {
+    previous: Option<Arc<dyn HalBackend>>,
+}
+
+impl Drop for ScopedBackend {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        THREAD_BACKEND.with(|slot| *slot.borrow_mut() = previous);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn platform_backend() -> Arc<dyn HalBackend> {
+    Arc::new(CoreAudioHal)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn platform_backend() -> Arc<dyn HalBackend> {
+    Arc::new(SimulatedHal::new())
+}
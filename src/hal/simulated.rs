@@ -0,0 +1,938 @@
+use super::{HalBackend, ListenerProc};
+use crate::{
+    aopa::AudioObjPropAddress,
+    devices::{ChannelLabel, ChannelLayout, StreamFormat, TerminalType, TransportType},
+    error::{Error, HalError},
+    mscope::PropertyScope,
+    mselector::PropertySelector,
+    property::{
+        as_bytes, AudioStreamBasicDescription, AudioStreamRangedDescription, AudioValueRange,
+    },
+    sys::{
+        kAudioHardwareBadObjectError, kAudioHardwareBadPropertySizeError,
+        kAudioHardwareIllegalOperationError, kAudioHardwareUnknownPropertyError,
+        kAudioObjectSystemObject, kAudioObjectUnknown, AudioBuffer, AudioDeviceID, AudioObjectID,
+        AudioObjectPropertyAddress, OSStatus,
+    },
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    ffi::c_void,
+    mem::{align_of, size_of},
+    ptr, slice,
+    sync::{Mutex, MutexGuard},
+};
+
+const FIRST_OBJECT_ID: AudioObjectID = 100;
+const DEVICE_LIST: AudioObjPropAddress =
+    AudioObjPropAddress::new(PropertySelector::HW_ALL_DEVICES, PropertyScope::OBJ_GLOBAL);
+
+/// Description of a device to be added to a [`SimulatedHal`].
+#[derive(Debug, Clone)]
+pub struct SimDevice {
+    name: String,
+    uid: Option<String>,
+    model_uid: Option<String>,
+    manufacturer: Option<String>,
+    input_channels: u32,
+    output_channels: u32,
+    volume: f32,
+    muted: bool,
+    master_volume: bool,
+    channel_volume: bool,
+    decibel_range: (f32, f32),
+    sample_rate: f64,
+    sample_rates: Vec<f64>,
+    buffer_frame_size: u32,
+    buffer_frame_size_range: (u32, u32),
+    latency: u32,
+    safety_offset: u32,
+    stream_latency: u32,
+    transport_type: TransportType,
+    hidden: bool,
+    output_layout: Option<Vec<ChannelLabel>>,
+}
+
+impl SimDevice {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            uid: None,
+            model_uid: None,
+            manufacturer: None,
+            input_channels: 0,
+            output_channels: 0,
+            volume: 0.5,
+            muted: false,
+            master_volume: false,
+            channel_volume: true,
+            decibel_range: (-64.0, 0.0),
+            sample_rate: 48_000.0,
+            sample_rates: vec![44_100.0, 48_000.0, 96_000.0],
+            buffer_frame_size: 512,
+            buffer_frame_size_range: (15, 4096),
+            latency: 0,
+            safety_offset: 0,
+            stream_latency: 0,
+            transport_type: TransportType::BuiltIn,
+            hidden: false,
+            output_layout: None,
+        }
+    }
+
+    /// Persistent UID of the device. Defaults to one derived from its object ID.
+    pub fn uid(mut self, uid: &str) -> Self {
+        self.uid = Some(uid.into());
+        self
+    }
+
+    pub fn model_uid(mut self, model_uid: &str) -> Self {
+        self.model_uid = Some(model_uid.into());
+        self
+    }
+
+    pub fn manufacturer(mut self, manufacturer: &str) -> Self {
+        self.manufacturer = Some(manufacturer.into());
+        self
+    }
+
+    pub fn input_channels(mut self, channels: u32) -> Self {
+        self.input_channels = channels;
+        self
+    }
+
+    pub fn output_channels(mut self, channels: u32) -> Self {
+        self.output_channels = channels;
+        self
+    }
+
+    /// Initial scalar volume of every channel.
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn muted(mut self, muted: bool) -> Self {
+        self.muted = muted;
+        self
+    }
+
+    /// Whether the device also exposes a volume control on the master element.
+    pub fn master_volume(mut self, enabled: bool) -> Self {
+        self.master_volume = enabled;
+        self
+    }
+
+    /// Whether every channel has its own volume control.
+    pub fn channel_volume(mut self, enabled: bool) -> Self {
+        self.channel_volume = enabled;
+        self
+    }
+
+    /// Range of every volume control in decibels.
+    pub fn decibel_range(mut self, min: f32, max: f32) -> Self {
+        self.decibel_range = (min, max);
+        self
+    }
+
+    pub fn nominal_sample_rate(mut self, rate: f64) -> Self {
+        self.sample_rate = rate;
+        self
+    }
+
+    /// Discrete sample rates the device accepts.
+    pub fn sample_rates(mut self, rates: &[f64]) -> Self {
+        self.sample_rates = rates.to_vec();
+        self
+    }
+
+    pub fn buffer_frame_size(mut self, size: u32) -> Self {
+        self.buffer_frame_size = size;
+        self
+    }
+
+    pub fn buffer_frame_size_range(mut self, min: u32, max: u32) -> Self {
+        self.buffer_frame_size_range = (min, max);
+        self
+    }
+
+    /// Device latency in frames, in both directions.
+    pub fn latency(mut self, frames: u32) -> Self {
+        self.latency = frames;
+        self
+    }
+
+    pub fn safety_offset(mut self, frames: u32) -> Self {
+        self.safety_offset = frames;
+        self
+    }
+
+    /// Latency of every stream, in frames.
+    pub fn stream_latency(mut self, frames: u32) -> Self {
+        self.stream_latency = frames;
+        self
+    }
+
+    pub fn transport_type(mut self, transport_type: TransportType) -> Self {
+        self.transport_type = transport_type;
+        self
+    }
+
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Labels of the output channels. By default one and two channel devices
+    /// are mono and stereo, and other devices have discrete channels.
+    pub fn output_layout(mut self, labels: &[ChannelLabel]) -> Self {
+        self.output_layout = Some(labels.to_vec());
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Property {
+    data: Vec<u8>,
+    settable: bool,
+}
+
+/// A scripted disturbance of a property read, see
+/// [`SimulatedHal::script_read`].
+#[derive(Debug, Clone)]
+pub enum ReadFault {
+    /// The read fails with `kAudioHardwareBadPropertySizeError`.
+    BadPropertySize,
+    /// The property takes the new value right before it is read, as if it
+    /// changed after its size was queried. Listeners are not notified.
+    Change(Vec<u8>),
+}
+
+#[derive(Debug)]
+struct Listener {
+    object: AudioObjectID,
+    address: AudioObjPropAddress,
+    proc_: ListenerProc,
+    client_data: usize,
+}
+
+type Properties = HashMap<AudioObjPropAddress, Property>;
+
+#[derive(Debug)]
+struct State {
+    next_id: AudioObjectID,
+    objects: HashMap<AudioObjectID, Properties>,
+    listeners: Vec<Listener>,
+    faults: HashMap<(AudioObjectID, AudioObjPropAddress), VecDeque<ReadFault>>,
+}
+
+/// An in-memory audio system whose objects and properties are scripted by the caller.
+///
+/// Property lookups first try the exact address and then fall back to the same
+/// selector and element in the global scope, like most device properties do in
+/// CoreAudio. Listeners are invoked synchronously on the thread that changed the
+/// property.
+#[derive(Debug)]
+pub struct SimulatedHal {
+    state: Mutex<State>,
+}
+
+impl SimulatedHal {
+    pub fn new() -> Self {
+        let mut system = Properties::new();
+        let global = |select| AudioObjPropAddress::new(select, PropertyScope::OBJ_GLOBAL);
+
+        system.insert(DEVICE_LIST, Property::fixed(Vec::new()));
+        system.insert(
+            global(PropertySelector::HW_DEFAULT_INPUT_DEV),
+            Property::settable(0u32.to_ne_bytes().into()),
+        );
+        system.insert(
+            global(PropertySelector::HW_DEFAULT_OUTPUT_DEV),
+            Property::settable(0u32.to_ne_bytes().into()),
+        );
+
+        Self {
+            state: Mutex::new(State {
+                next_id: FIRST_OBJECT_ID,
+                objects: HashMap::from([(kAudioObjectSystemObject, system)]),
+                listeners: Vec::new(),
+                faults: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Adds a device to the system and returns its newly assigned ID.
+    pub fn add_device(&self, device: SimDevice) -> AudioDeviceID {
+        let mut state = self.lock();
+        let id = state.allocate_id();
+        let mut props = Properties::new();
+
+        let global = |select| AudioObjPropAddress::new(select, PropertyScope::OBJ_GLOBAL);
+        let uid = device
+            .uid
+            .unwrap_or_else(|| format!("SimulatedDevice:{id}"));
+
+        props.insert(
+            global(PropertySelector::DEV_OBJECT_NAME),
+            Property::settable(device.name.into_bytes()),
+        );
+        props.insert(
+            global(PropertySelector::DEV_UID),
+            Property::fixed(uid.into_bytes()),
+        );
+
+        props.insert(
+            global(PropertySelector::DEV_NOMINAL_SAMPLE_RATE),
+            Property::settable(device.sample_rate.to_ne_bytes().into()),
+        );
+        props.insert(
+            global(PropertySelector::DEV_AVAILABLE_NOMINAL_SAMPLE_RATES),
+            Property::fixed(
+                device
+                    .sample_rates
+                    .iter()
+                    .flat_map(|rate| [rate.to_ne_bytes(), rate.to_ne_bytes()])
+                    .flatten()
+                    .collect(),
+            ),
+        );
+
+        props.insert(
+            global(PropertySelector::DEV_BUFFER_FRAME_SIZE),
+            Property::settable(device.buffer_frame_size.to_ne_bytes().into()),
+        );
+        let (min_frames, max_frames) = device.buffer_frame_size_range;
+        props.insert(
+            global(PropertySelector::DEV_BUFFER_FRAME_SIZE_RANGE),
+            Property::fixed(
+                [f64::from(min_frames), f64::from(max_frames)]
+                    .iter()
+                    .flat_map(|frames| frames.to_ne_bytes())
+                    .collect(),
+            ),
+        );
+
+        for (selector, value) in [
+            (
+                PropertySelector::DEV_TRANSPORT_TYPE,
+                device.transport_type.raw(),
+            ),
+            (PropertySelector::DEV_IS_HIDDEN, u32::from(device.hidden)),
+            (PropertySelector::DEV_IS_ALIVE, 1),
+            (PropertySelector::DEV_IS_RUNNING, 0),
+            (PropertySelector::DEV_CAN_BE_DEFAULT, 1),
+        ] {
+            props.insert(
+                global(selector),
+                Property::fixed(value.to_ne_bytes().into()),
+            );
+        }
+
+        for (selector, value) in [
+            (PropertySelector::DEV_MODEL_UID, device.model_uid),
+            (PropertySelector::DEV_MANUFACTURER, device.manufacturer),
+        ] {
+            if let Some(value) = value {
+                props.insert(global(selector), Property::fixed(value.into_bytes()));
+            }
+        }
+
+        for (scope, channels) in [
+            (PropertyScope::DEV_INPUT, device.input_channels),
+            (PropertyScope::DEV_OUTPUT, device.output_channels),
+        ] {
+            let mut streams = Vec::new();
+
+            if channels > 0 {
+                let stream_id = state.allocate_id();
+                let (direction, terminal) = match scope {
+                    PropertyScope::DEV_INPUT => (1u32, TerminalType::Microphone),
+                    _ => (0, TerminalType::Speaker),
+                };
+                let linear_pcm = |rate, bits, float| -> AudioStreamBasicDescription {
+                    StreamFormat::linear_pcm(rate, channels, bits, float, true).into()
+                };
+                let virtual_format = linear_pcm(device.sample_rate, 32, true);
+                let physical_format = linear_pcm(device.sample_rate, 24, false);
+                let ranged = |bits, float| -> Vec<AudioStreamRangedDescription> {
+                    device
+                        .sample_rates
+                        .iter()
+                        .map(|rate| AudioStreamRangedDescription {
+                            mFormat: linear_pcm(*rate, bits, float),
+                            mSampleRateRange: AudioValueRange {
+                                mMinimum: *rate,
+                                mMaximum: *rate,
+                            },
+                        })
+                        .collect()
+                };
+                let virtual_formats = ranged(32, true);
+                let physical_formats = [ranged(16, false), ranged(24, false)].concat();
+
+                let mut stream = Properties::new();
+                for (selector, value) in [
+                    (PropertySelector::STM_LATENCY, device.stream_latency),
+                    (PropertySelector::STM_DIRECTION, direction),
+                    (PropertySelector::STM_STARTING_CHANNEL, 1),
+                    (PropertySelector::STM_TERMINAL_TYPE, terminal.raw()),
+                    (PropertySelector::STM_IS_ACTIVE, 1),
+                ] {
+                    stream.insert(
+                        global(selector),
+                        Property::fixed(value.to_ne_bytes().into()),
+                    );
+                }
+                for (selector, format) in [
+                    (PropertySelector::STM_VIRTUAL_FORMAT, virtual_format),
+                    (PropertySelector::STM_PHYSICAL_FORMAT, physical_format),
+                ] {
+                    stream.insert(
+                        global(selector),
+                        Property::settable(as_bytes(slice::from_ref(&format)).into()),
+                    );
+                }
+                for (selector, formats) in [
+                    (
+                        PropertySelector::STM_AVAILABLE_VIRTUAL_FORMATS,
+                        virtual_formats,
+                    ),
+                    (
+                        PropertySelector::STM_AVAILABLE_PHYSICAL_FORMATS,
+                        physical_formats,
+                    ),
+                ] {
+                    stream.insert(global(selector), Property::fixed(as_bytes(&formats).into()));
+                }
+                state.objects.insert(stream_id, stream);
+                streams.extend_from_slice(&stream_id.to_ne_bytes());
+
+                props.insert(
+                    AudioObjPropAddress::new(PropertySelector::DEV_MUTE, scope),
+                    Property::settable(u32::from(device.muted).to_ne_bytes().into()),
+                );
+            }
+
+            props.insert(
+                AudioObjPropAddress::new(PropertySelector::DEV_STREAMS, scope),
+                Property::fixed(streams),
+            );
+            let buffers: &[u32] = if channels > 0 { &[channels] } else { &[] };
+            props.insert(
+                AudioObjPropAddress::new(PropertySelector::DEV_STREAM_CONFIGURATION, scope),
+                Property::fixed(buffer_list(buffers)),
+            );
+            if channels > 0 {
+                let layout = match (scope, &device.output_layout) {
+                    (PropertyScope::DEV_OUTPUT, Some(labels)) => labels.clone(),
+                    _ => default_layout(channels),
+                };
+
+                props.insert(
+                    AudioObjPropAddress::new(
+                        PropertySelector::DEV_PREFERRED_STEREO_CHANNELS,
+                        scope,
+                    ),
+                    Property::settable(as_bytes(&[1, channels.min(2)]).into()),
+                );
+                props.insert(
+                    AudioObjPropAddress::new(PropertySelector::DEV_PREFERRED_CHANNEL_LAYOUT, scope),
+                    Property::fixed(ChannelLayout::from_labels(&layout).to_bytes()),
+                );
+            }
+
+            props.insert(
+                AudioObjPropAddress::new(PropertySelector::DEV_LATENCY, scope),
+                Property::fixed(device.latency.to_ne_bytes().into()),
+            );
+            props.insert(
+                AudioObjPropAddress::new(PropertySelector::DEV_SAFETY_OFFSET, scope),
+                Property::fixed(device.safety_offset.to_ne_bytes().into()),
+            );
+
+            let first = if device.master_volume { 0 } else { 1 };
+            let last = if device.channel_volume { channels } else { 0 };
+            let (min_db, max_db) = device.decibel_range;
+            for channel in first..=last {
+                props.insert(
+                    AudioObjPropAddress::new_with_element(
+                        PropertySelector::DEV_VOLUME_SCALAR,
+                        scope,
+                        channel,
+                    ),
+                    Property::settable(device.volume.to_ne_bytes().into()),
+                );
+                props.insert(
+                    AudioObjPropAddress::new_with_element(
+                        PropertySelector::DEV_VOLUME_RANGE_DECIBELS,
+                        scope,
+                        channel,
+                    ),
+                    Property::fixed(as_bytes(&[f64::from(min_db), f64::from(max_db)]).into()),
+                );
+            }
+        }
+
+        state.objects.insert(id, props);
+        state
+            .system_property(DEVICE_LIST)
+            .data
+            .extend_from_slice(&id.to_ne_bytes());
+
+        self.notify(state, kAudioObjectSystemObject, &[DEVICE_LIST]);
+        id
+    }
+
+    /// Removes a device from the system, notifying listeners of the device list.
+    pub fn remove_device(&self, id: AudioDeviceID) {
+        let mut state = self.lock();
+        state.objects.remove(&id);
+
+        let list = &mut state.system_property(DEVICE_LIST).data;
+        *list = list
+            .chunks_exact(size_of::<AudioDeviceID>())
+            .filter(|chunk| *chunk != id.to_ne_bytes())
+            .flatten()
+            .copied()
+            .collect();
+
+        self.notify(state, kAudioObjectSystemObject, &[DEVICE_LIST]);
+    }
+
+    pub fn set_default_input(&self, id: AudioDeviceID) {
+        self.update_property(
+            kAudioObjectSystemObject,
+            AudioObjPropAddress::new(
+                PropertySelector::HW_DEFAULT_INPUT_DEV,
+                PropertyScope::OBJ_GLOBAL,
+            ),
+            id.to_ne_bytes(),
+        );
+    }
+
+    pub fn set_default_output(&self, id: AudioDeviceID) {
+        self.update_property(
+            kAudioObjectSystemObject,
+            AudioObjPropAddress::new(
+                PropertySelector::HW_DEFAULT_OUTPUT_DEV,
+                PropertyScope::OBJ_GLOBAL,
+            ),
+            id.to_ne_bytes(),
+        );
+    }
+
+    /// Queues `fault` for the next read of the property at `address`. Faults
+    /// queued for the same property apply to consecutive reads, in order.
+    pub fn script_read(&self, id: AudioObjectID, address: AudioObjPropAddress, fault: ReadFault) {
+        self.lock()
+            .faults
+            .entry((id, address))
+            .or_default()
+            .push_back(fault);
+    }
+
+    /// Defines (or redefines) a property without notifying listeners.
+    pub fn define_property(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        data: impl Into<Vec<u8>>,
+        settable: bool,
+    ) {
+        let mut state = self.lock();
+        let property = Property {
+            data: data.into(),
+            settable,
+        };
+
+        state
+            .objects
+            .entry(id)
+            .or_default()
+            .insert(address, property);
+    }
+
+    /// Changes a property as if the hardware did it, notifying listeners.
+    pub fn update_property(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        data: impl Into<Vec<u8>>,
+    ) {
+        self.update_properties(id, [(address, data.into())]);
+    }
+
+    /// Changes several properties of one object at once and only then notifies
+    /// listeners, the way the HAL reports a batch of related changes.
+    pub fn update_properties(
+        &self,
+        id: AudioObjectID,
+        changes: impl IntoIterator<Item = (AudioObjPropAddress, Vec<u8>)>,
+    ) {
+        let mut state = self.lock();
+        let props = state.objects.entry(id).or_default();
+        let mut changed = Vec::new();
+
+        for (address, data) in changes {
+            let key = resolve(props, address).unwrap_or(address);
+
+            props
+                .entry(key)
+                .or_insert_with(|| Property::fixed(Vec::new()))
+                .data = data;
+            changed.push(key);
+        }
+
+        self.notify(state, id, &changed);
+    }
+
+    /// Returns the raw bytes currently stored for a property.
+    pub fn property_data(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Option<Vec<u8>> {
+        let state = self.lock();
+        let props = state.objects.get(&id)?;
+
+        resolve(props, address).map(|key| props[&key].data.clone())
+    }
+
+    /// Writes a settable property on behalf of a client, notifying listeners.
+    fn store(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        data: &[u8],
+        fixed_size: bool,
+    ) -> Result<(), Error> {
+        let mut state = self.lock();
+        let props = state
+            .objects
+            .get_mut(&id)
+            .ok_or_else(|| hal_error(kAudioHardwareBadObjectError, id, address))?;
+        let key = resolve(props, address)
+            .ok_or_else(|| hal_error(kAudioHardwareUnknownPropertyError, id, address))?;
+        let property = props.get_mut(&key).unwrap();
+
+        if !property.settable {
+            return Err(hal_error(kAudioHardwareIllegalOperationError, id, address));
+        }
+
+        if fixed_size && property.data.len() != data.len() {
+            return Err(hal_error(kAudioHardwareBadPropertySizeError, id, address));
+        }
+
+        property.data = data.to_vec();
+
+        self.notify(state, id, &[key]);
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Invokes every listener registered for one of `changed`, after releasing the lock
+    /// so that listeners are free to query the HAL again.
+    fn notify(
+        &self,
+        state: MutexGuard<'_, State>,
+        id: AudioObjectID,
+        changed: &[AudioObjPropAddress],
+    ) {
+        let Some(props) = state.objects.get(&id) else {
+            return;
+        };
+
+        let pending: Vec<_> = state
+            .listeners
+            .iter()
+            .filter(|listener| listener.object == id)
+            .filter(|listener| {
+                resolve(props, listener.address).is_some_and(|key| changed.contains(&key))
+            })
+            .map(|listener| (listener.proc_, listener.address, listener.client_data))
+            .collect();
+
+        drop(state);
+
+        for (proc_, address, client_data) in pending {
+            let raw: AudioObjectPropertyAddress = address.into();
+            unsafe { proc_(id, 1, &raw, client_data as *mut c_void) };
+        }
+    }
+}
+
+impl Default for SimulatedHal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HalBackend for SimulatedHal {
+    fn has_property(&self, id: AudioObjectID, address: AudioObjPropAddress) -> bool {
+        let state = self.lock();
+
+        state
+            .objects
+            .get(&id)
+            .and_then(|props| resolve(props, address))
+            .is_some()
+    }
+
+    fn is_property_settable(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<bool, Error> {
+        Ok(self.lock().property(id, address)?.settable)
+    }
+
+    fn get_property_data_size(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<usize, Error> {
+        let state = self.lock();
+
+        Ok(state.property(id, address)?.data.len())
+    }
+
+    fn get_property_data(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        data: &mut [u8],
+    ) -> Result<usize, Error> {
+        let mut state = self.lock();
+        let fault = state
+            .faults
+            .get_mut(&(id, address))
+            .and_then(VecDeque::pop_front);
+
+        match fault {
+            Some(ReadFault::BadPropertySize) => {
+                return Err(hal_error(kAudioHardwareBadPropertySizeError, id, address));
+            }
+            Some(ReadFault::Change(value)) => {
+                state.property(id, address)?;
+                let props = state.objects.get_mut(&id).unwrap();
+                let key = resolve(props, address).unwrap();
+                props.get_mut(&key).unwrap().data = value;
+            }
+            None => {}
+        }
+
+        let property = state.property(id, address)?;
+        let len = property.data.len().min(data.len());
+
+        data[..len].copy_from_slice(&property.data[..len]);
+
+        Ok(len)
+    }
+
+    fn set_property_data(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        self.store(id, address, data, true)
+    }
+
+    /// Only `kAudioHardwarePropertyTranslateUIDToDevice` is supported; it
+    /// yields `kAudioObjectUnknown` when no device has the given UID.
+    fn get_property_data_with_string_qualifier(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        qualifier: &str,
+        data: &mut [u8],
+    ) -> Result<usize, Error> {
+        if id != kAudioObjectSystemObject
+            || address.selector() != PropertySelector::HW_TRANSLATE_UID_TO_DEVICE
+        {
+            return Err(hal_error(kAudioHardwareUnknownPropertyError, id, address));
+        }
+
+        if data.len() != size_of::<AudioDeviceID>() {
+            return Err(hal_error(kAudioHardwareBadPropertySizeError, id, address));
+        }
+
+        let state = self.lock();
+        let uid = AudioObjPropAddress::new(PropertySelector::DEV_UID, PropertyScope::OBJ_GLOBAL);
+        let device = state
+            .devices()
+            .find(|device| {
+                state
+                    .property(*device, uid)
+                    .is_ok_and(|property| property.data == qualifier.as_bytes())
+            })
+            .unwrap_or(kAudioObjectUnknown);
+
+        data.copy_from_slice(&device.to_ne_bytes());
+
+        Ok(data.len())
+    }
+
+    /// Strings are stored as their UTF-8 bytes.
+    fn get_string_property(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<String, Error> {
+        let state = self.lock();
+        let data = state.property(id, address)?.data.clone();
+
+        String::from_utf8(data).map_err(|err| err.utf8_error().into())
+    }
+
+    fn set_string_property(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        value: &str,
+    ) -> Result<(), Error> {
+        self.store(id, address, value.as_bytes(), false)
+    }
+
+    fn add_property_listener(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        listener: ListenerProc,
+        client_data: *mut c_void,
+    ) -> Result<(), Error> {
+        let mut state = self.lock();
+
+        if !state.objects.contains_key(&id) {
+            return Err(hal_error(kAudioHardwareBadObjectError, id, address));
+        }
+
+        state.listeners.push(Listener {
+            object: id,
+            address,
+            proc_: listener,
+            client_data: client_data as usize,
+        });
+
+        Ok(())
+    }
+
+    fn remove_property_listener(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+        listener: ListenerProc,
+        client_data: *mut c_void,
+    ) -> Result<(), Error> {
+        let mut state = self.lock();
+        let position = state.listeners.iter().position(|entry| {
+            entry.object == id
+                && entry.address == address
+                && ptr::fn_addr_eq(entry.proc_, listener)
+                && entry.client_data == client_data as usize
+        });
+
+        if let Some(position) = position {
+            state.listeners.remove(position);
+        }
+
+        Ok(())
+    }
+}
+
+impl Property {
+    fn fixed(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            settable: false,
+        }
+    }
+
+    fn settable(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            settable: true,
+        }
+    }
+}
+
+impl State {
+    fn allocate_id(&mut self) -> AudioObjectID {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn property(
+        &self,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<&Property, Error> {
+        let props = self
+            .objects
+            .get(&id)
+            .ok_or_else(|| hal_error(kAudioHardwareBadObjectError, id, address))?;
+        let key = resolve(props, address)
+            .ok_or_else(|| hal_error(kAudioHardwareUnknownPropertyError, id, address))?;
+
+        Ok(&props[&key])
+    }
+
+    fn devices(&self) -> impl Iterator<Item = AudioDeviceID> + '_ {
+        self.objects[&kAudioObjectSystemObject][&DEVICE_LIST]
+            .data
+            .chunks_exact(size_of::<AudioDeviceID>())
+            .map(|chunk| AudioDeviceID::from_ne_bytes(chunk.try_into().unwrap()))
+    }
+
+    fn system_property(&mut self, address: AudioObjPropAddress) -> &mut Property {
+        self.objects
+            .get_mut(&kAudioObjectSystemObject)
+            .and_then(|props| props.get_mut(&address))
+            .unwrap()
+    }
+}
+
+fn resolve(props: &Properties, address: AudioObjPropAddress) -> Option<AudioObjPropAddress> {
+    let global = AudioObjPropAddress::new_with_element(
+        address.selector(),
+        PropertyScope::OBJ_GLOBAL,
+        address.element(),
+    );
+
+    [address, global]
+        .into_iter()
+        .find(|key| props.contains_key(key))
+}
+
+/// An `AudioBufferList` with one buffer per entry of `channels`.
+fn buffer_list(channels: &[u32]) -> Vec<u8> {
+    let mut data = (channels.len() as u32).to_ne_bytes().to_vec();
+    data.resize(align_of::<AudioBuffer>(), 0);
+
+    for count in channels {
+        let start = data.len();
+        data.extend_from_slice(&count.to_ne_bytes());
+        data.resize(start + size_of::<AudioBuffer>(), 0);
+    }
+
+    data
+}
+
+fn default_layout(channels: u32) -> Vec<ChannelLabel> {
+    match channels {
+        1 => vec![ChannelLabel::Mono],
+        2 => vec![ChannelLabel::Left, ChannelLabel::Right],
+        _ => (0..channels as u16).map(ChannelLabel::Discrete).collect(),
+    }
+}
+
+fn hal_error(code: u32, id: AudioObjectID, address: AudioObjPropAddress) -> Error {
+    HalError::for_property(code as OSStatus, id, address).into()
+}
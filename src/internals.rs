@@ -1,131 +1,136 @@
 use crate::{
     aopa::AudioObjPropAddress,
-    error::Error,
+    error::{Error, HalError},
+    hal,
     mscope::PropertyScope,
-    mselector::{AudioDevPropSelector, PropertySelector},
-};
-use coreaudio_sys::{
-    AudioDeviceID, AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize,
-    AudioObjectHasProperty, AudioObjectSetPropertyData, AudioStreamID, UInt32,
-};
-use std::{
-    ffi::{c_void, CStr},
-    mem::size_of,
-    ptr::{self, addr_of, null},
+    mselector::PropertySelector,
+    property::{self, PlainData, PropertyValue},
+    sys::{
+        kAudioHardwareBadPropertySizeError, kAudioObjectSystemObject, kAudioObjectUnknown,
+        AudioBuffer, AudioDeviceID, AudioObjectID, AudioStreamID, OSStatus,
+    },
 };
+use std::{mem, slice};
 
-const CHANNEL_CHECK_FAILS: usize = 3;
-const DEVICE_NAME_LEN: usize = 256;
+pub fn get_property<T: PropertyValue>(
+    device_id: AudioDeviceID,
+    property: AudioObjPropAddress,
+) -> Result<T, Error> {
+    T::read(&*hal::backend(), device_id, property)
+}
 
-pub fn get_property_complex<T>(
+pub fn set_property<T: PropertyValue>(
     device_id: AudioDeviceID,
     property: AudioObjPropAddress,
-    data_ptr: *mut T,
-    data_size: usize,
+    value: &T,
 ) -> Result<(), Error> {
-    let ptr = data_ptr.cast::<c_void>();
-    let mut data_size = u32::try_from(data_size)?;
+    value.write(&*hal::backend(), device_id, property)
+}
 
-    let status = unsafe {
-        AudioObjectGetPropertyData(device_id, &property.into(), 0, null(), &mut data_size, ptr)
-    };
+/// Reads a translation property, which converts `value` in place.
+pub fn translate_property<T: PlainData>(
+    id: AudioObjectID,
+    address: AudioObjPropAddress,
+    mut value: T,
+) -> Result<T, Error> {
+    hal::backend().get_property_data(
+        id,
+        address,
+        property::as_bytes_mut(slice::from_mut(&mut value)),
+    )?;
 
-    if status != 0 {
-        return Err(status.into());
-    }
+    Ok(value)
+}
 
-    Ok(())
+pub fn has_property(device_id: AudioDeviceID, property: AudioObjPropAddress) -> bool {
+    hal::backend().has_property(device_id, property)
 }
 
-pub fn get_property<T: Default + Sized>(
-    device_id: AudioDeviceID,
+pub fn is_property_settable(
+    id: AudioObjectID,
     property: AudioObjPropAddress,
-) -> Result<T, Error> {
-    let mut data = T::default();
-    let data_ptr = ptr::from_mut(&mut data);
-    let data_size = size_of::<T>();
+) -> Result<bool, Error> {
+    hal::backend().is_property_settable(id, property)
+}
 
-    get_property_complex(device_id, property, data_ptr, data_size)?;
+/// Total number of channels of the streams of `id` in `scope`, read from its
+/// `AudioBufferList` stream configuration.
+pub fn get_channel_count(id: AudioDeviceID, scope: PropertyScope) -> Result<u32, Error> {
+    let address = property::STREAM_CONFIGURATION.address(scope, 0);
+    let data: Vec<u8> = get_property(id, address)?;
 
-    Ok(data)
+    buffer_list_channels(&data).ok_or_else(|| {
+        HalError::for_property(kAudioHardwareBadPropertySizeError as OSStatus, id, address).into()
+    })
 }
 
-pub fn set_property<T>(
-    device_id: AudioDeviceID,
-    property: AudioObjPropAddress,
-    value: &T,
-) -> Result<(), Error> {
-    let ptr = addr_of!(*value) as *mut c_void;
-    let data_size = size_of::<T>() as UInt32;
-
-    let status = unsafe {
-        AudioObjectSetPropertyData(device_id, &property.into(), 0, null(), data_size, ptr)
+/// Sums `mNumberChannels` over the buffers of an `AudioBufferList`.
+fn buffer_list_channels(data: &[u8]) -> Option<u32> {
+    // `mBuffers` is aligned like the pointer in `AudioBuffer`.
+    let header = mem::align_of::<AudioBuffer>();
+    let stride = mem::size_of::<AudioBuffer>();
+    let word = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
     };
 
-    if status != 0 {
-        return Err(status.into());
+    let count = usize::try_from(word(0)?).ok()?;
+    if data.len() < count.checked_mul(stride)?.checked_add(header)? {
+        return None;
     }
-    Ok(())
-}
 
-pub fn has_property(device_id: AudioDeviceID, property: AudioObjPropAddress) -> bool {
-    let ret = unsafe { AudioObjectHasProperty(device_id, &property.into()) };
-    ret != 0
+    (0..count).try_fold(0u32, |total, index| {
+        total.checked_add(word(header + index * stride)?)
+    })
 }
 
-pub fn get_valid_channels(id: AudioDeviceID, scope: PropertyScope) -> Vec<u32> {
-    let mut result = Vec::new();
-    let mut address = AudioObjPropAddress::new(
-        PropertySelector::Device(AudioDevPropSelector::VolumeScalar),
-        scope,
-    );
-    let mut failures = 0;
+/// Mute state of a scope: the master mute if there is one, otherwise whether
+/// every channel in `elements` is muted.
+pub fn get_muted(id: AudioDeviceID, scope: PropertyScope, elements: &[u32]) -> Result<bool, Error> {
+    if elements.is_empty() {
+        return Err(Error::NoMuteControl);
+    }
+
+    if elements.contains(&0) {
+        return get_property(id, property::MUTE.address(scope, 0));
+    }
 
-    while failures < CHANNEL_CHECK_FAILS {
-        if has_property(id, address) {
-            result.push(address.element());
-        } else {
-            failures += 1;
+    for element in elements {
+        if !get_property::<bool>(id, property::MUTE.address(scope, *element))? {
+            return Ok(false);
         }
-        address.set_element(address.element() + 1);
     }
 
-    result
+    Ok(true)
 }
 
-pub fn get_property_data_size(
-    id: AudioDeviceID,
+pub fn get_property_array<T: PlainData>(
+    id: AudioObjectID,
     address: AudioObjPropAddress,
-) -> Result<usize, Error> {
-    let mut size = 0u32;
-
-    let status =
-        unsafe { AudioObjectGetPropertyDataSize(id, &address.into(), 0, null(), &mut size) };
-
-    if status != 0 {
-        return Err(status.into());
-    }
-
-    Ok(usize::try_from(size)?)
+) -> Result<Vec<T>, Error> {
+    property::read_array(&*hal::backend(), id, address)
 }
 
-pub fn get_device_name(id: AudioDeviceID, scope: PropertyScope) -> Result<String, Error> {
-    let address = AudioObjPropAddress::new(PropertySelector::DEV_NAME, scope);
-    let mut name_buf = [0u8; DEVICE_NAME_LEN];
-    let ptr = name_buf.as_mut_ptr().cast::<c_void>();
-    let mut data_size = DEVICE_NAME_LEN as UInt32;
-
-    let status =
-        unsafe { AudioObjectGetPropertyData(id, &address.into(), 0, null(), &mut data_size, ptr) };
+pub fn get_device_name(id: AudioDeviceID) -> Result<String, Error> {
+    get_property(id, property::NAME.address(PropertyScope::OBJ_GLOBAL, 0))
+}
 
-    if status != 0 {
-        return Err(Error::Raw(status));
-    }
+/// Looks up the device currently carrying `uid`, if it is connected.
+pub fn translate_uid(uid: &str) -> Result<Option<AudioDeviceID>, Error> {
+    let address = AudioObjPropAddress::new(
+        PropertySelector::HW_TRANSLATE_UID_TO_DEVICE,
+        PropertyScope::OBJ_GLOBAL,
+    );
+    let mut id: AudioDeviceID = kAudioObjectUnknown;
 
-    let name = CStr::from_bytes_until_nul(&name_buf)?;
-    let name = name.to_str()?;
+    hal::backend().get_property_data_with_string_qualifier(
+        kAudioObjectSystemObject,
+        address,
+        uid,
+        property::as_bytes_mut(slice::from_mut(&mut id)),
+    )?;
 
-    Ok(name.into())
+    Ok((id != kAudioObjectUnknown).then_some(id))
 }
 
 pub fn get_streams(id: AudioDeviceID, scope: PropertyScope) -> Result<u32, Error> {
@@ -134,9 +139,7 @@ pub fn get_streams(id: AudioDeviceID, scope: PropertyScope) -> Result<u32, Error
     }
 
     let address = AudioObjPropAddress::new(PropertySelector::DEV_STREAMS, scope);
+    let streams = get_property_array::<AudioStreamID>(id, address)?;
 
-    let stream_count_bytes: usize = get_property::<u32>(id, address)?.try_into()?;
-    let stream_count = stream_count_bytes / size_of::<AudioStreamID>();
-
-    Ok(stream_count.try_into()?)
+    Ok(streams.len().try_into()?)
 }
@@ -2,6 +2,9 @@ pub mod aopa;
 pub mod devices;
 pub mod error;
 pub mod events;
+pub mod hal;
 pub(crate) mod internals;
 pub mod mscope;
 pub mod mselector;
+pub mod property;
+pub(crate) mod sys;
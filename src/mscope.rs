@@ -1,7 +1,11 @@
-use coreaudio_sys::{
-    kAudioDevicePropertyScopeInput, kAudioDevicePropertyScopeOutput,
-    kAudioDevicePropertyScopePlayThrough, kAudioObjectPropertyScopeGlobal,
+use crate::{
+    error::Error,
+    sys::{
+        kAudioDevicePropertyScopeInput, kAudioDevicePropertyScopeOutput,
+        kAudioDevicePropertyScopePlayThrough, kAudioObjectPropertyScopeGlobal,
+    },
 };
+use std::fmt::{self, Display, Formatter};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum AudioDevPropScope {
@@ -10,6 +14,10 @@ pub enum AudioDevPropScope {
     PlayThrough,
 }
 
+impl AudioDevPropScope {
+    pub const ALL: [Self; 3] = [Self::Input, Self::Output, Self::PlayThrough];
+}
+
 impl From<AudioDevPropScope> for u32 {
     fn from(value: AudioDevPropScope) -> Self {
         match value {
@@ -20,11 +28,26 @@ impl From<AudioDevPropScope> for u32 {
     }
 }
 
+impl TryFrom<u32> for AudioDevPropScope {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|item| u32::from(*item) == value)
+            .ok_or(Error::UnknownScope(value))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum AudioObjPropScope {
     Global,
 }
 
+impl AudioObjPropScope {
+    pub const ALL: [Self; 1] = [Self::Global];
+}
+
 impl From<AudioObjPropScope> for u32 {
     fn from(value: AudioObjPropScope) -> Self {
         match value {
@@ -33,6 +56,17 @@ impl From<AudioObjPropScope> for u32 {
     }
 }
 
+impl TryFrom<u32> for AudioObjPropScope {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|item| u32::from(*item) == value)
+            .ok_or(Error::UnknownScope(value))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum PropertyScope {
     Device(AudioDevPropScope),
@@ -42,6 +76,7 @@ pub enum PropertyScope {
 impl PropertyScope {
     pub const DEV_INPUT: Self = Self::Device(AudioDevPropScope::Input);
     pub const DEV_OUTPUT: Self = Self::Device(AudioDevPropScope::Output);
+    pub const DEV_PLAY_THROUGH: Self = Self::Device(AudioDevPropScope::PlayThrough);
 
     pub const OBJ_GLOBAL: Self = Self::Object(AudioObjPropScope::Global);
 }
@@ -54,3 +89,22 @@ impl From<PropertyScope> for u32 {
         }
     }
 }
+
+impl TryFrom<u32> for PropertyScope {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        AudioDevPropScope::try_from(value)
+            .map(Self::Device)
+            .or_else(|_| AudioObjPropScope::try_from(value).map(Self::Object))
+    }
+}
+
+impl Display for PropertyScope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Device(dev) => write!(f, "{dev:?}"),
+            Self::Object(obj) => write!(f, "{obj:?}"),
+        }
+    }
+}
@@ -1,8 +1,29 @@
-use coreaudio_sys::{
-    kAudioDevicePropertyDeviceName, kAudioDevicePropertyMute, kAudioDevicePropertyStreams,
-    kAudioDevicePropertyVolumeScalar, kAudioHardwarePropertyDefaultInputDevice,
-    kAudioHardwarePropertyDefaultOutputDevice, kAudioHardwarePropertyDevices,
+use crate::{
+    error::Error,
+    sys::{
+        kAudioDevicePropertyAvailableNominalSampleRates, kAudioDevicePropertyBufferFrameSize,
+        kAudioDevicePropertyBufferFrameSizeRange, kAudioDevicePropertyDataSource,
+        kAudioDevicePropertyDataSources, kAudioDevicePropertyDeviceCanBeDefaultDevice,
+        kAudioDevicePropertyDeviceIsAlive, kAudioDevicePropertyDeviceIsRunning,
+        kAudioDevicePropertyDeviceName, kAudioDevicePropertyDeviceUID,
+        kAudioDevicePropertyIsHidden, kAudioDevicePropertyLatency, kAudioDevicePropertyModelUID,
+        kAudioDevicePropertyMute, kAudioDevicePropertyNominalSampleRate,
+        kAudioDevicePropertyPreferredChannelLayout, kAudioDevicePropertyPreferredChannelsForStereo,
+        kAudioDevicePropertySafetyOffset, kAudioDevicePropertyStreamConfiguration,
+        kAudioDevicePropertyStreams, kAudioDevicePropertyTransportType,
+        kAudioDevicePropertyVolumeDecibels, kAudioDevicePropertyVolumeDecibelsToScalar,
+        kAudioDevicePropertyVolumeRangeDecibels, kAudioDevicePropertyVolumeScalar,
+        kAudioDevicePropertyVolumeScalarToDecibels, kAudioHardwarePropertyDefaultInputDevice,
+        kAudioHardwarePropertyDefaultOutputDevice, kAudioHardwarePropertyDevices,
+        kAudioHardwarePropertyTranslateUIDToDevice, kAudioObjectPropertyManufacturer,
+        kAudioObjectPropertyName, kAudioStreamPropertyAvailablePhysicalFormats,
+        kAudioStreamPropertyAvailableVirtualFormats, kAudioStreamPropertyDirection,
+        kAudioStreamPropertyIsActive, kAudioStreamPropertyLatency,
+        kAudioStreamPropertyPhysicalFormat, kAudioStreamPropertyStartingChannel,
+        kAudioStreamPropertyTerminalType, kAudioStreamPropertyVirtualFormat,
+    },
 };
+use std::fmt::{self, Display, Formatter};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum AudioDevPropSelector {
@@ -10,6 +31,63 @@ pub enum AudioDevPropSelector {
     Mute,
     Name,
     Streams,
+    AvailableNominalSampleRates,
+    DataSource,
+    DataSources,
+    ObjectName,
+    Manufacturer,
+    DeviceUID,
+    ModelUID,
+    NominalSampleRate,
+    BufferFrameSize,
+    BufferFrameSizeRange,
+    Latency,
+    SafetyOffset,
+    TransportType,
+    IsHidden,
+    IsAlive,
+    IsRunning,
+    CanBeDefault,
+    PreferredChannelsForStereo,
+    PreferredChannelLayout,
+    StreamConfiguration,
+    VolumeDecibels,
+    VolumeRangeDecibels,
+    VolumeScalarToDecibels,
+    VolumeDecibelsToScalar,
+}
+
+impl AudioDevPropSelector {
+    pub const ALL: [Self; 28] = [
+        Self::VolumeScalar,
+        Self::Mute,
+        Self::Name,
+        Self::Streams,
+        Self::AvailableNominalSampleRates,
+        Self::DataSource,
+        Self::DataSources,
+        Self::ObjectName,
+        Self::Manufacturer,
+        Self::DeviceUID,
+        Self::ModelUID,
+        Self::NominalSampleRate,
+        Self::BufferFrameSize,
+        Self::BufferFrameSizeRange,
+        Self::Latency,
+        Self::SafetyOffset,
+        Self::TransportType,
+        Self::IsHidden,
+        Self::IsAlive,
+        Self::IsRunning,
+        Self::CanBeDefault,
+        Self::PreferredChannelsForStereo,
+        Self::PreferredChannelLayout,
+        Self::StreamConfiguration,
+        Self::VolumeDecibels,
+        Self::VolumeRangeDecibels,
+        Self::VolumeScalarToDecibels,
+        Self::VolumeDecibelsToScalar,
+    ];
 }
 
 impl From<AudioDevPropSelector> for u32 {
@@ -19,15 +97,70 @@ impl From<AudioDevPropSelector> for u32 {
             AudioDevPropSelector::Mute => kAudioDevicePropertyMute,
             AudioDevPropSelector::Name => kAudioDevicePropertyDeviceName,
             AudioDevPropSelector::Streams => kAudioDevicePropertyStreams,
+            AudioDevPropSelector::AvailableNominalSampleRates => {
+                kAudioDevicePropertyAvailableNominalSampleRates
+            }
+            AudioDevPropSelector::DataSource => kAudioDevicePropertyDataSource,
+            AudioDevPropSelector::DataSources => kAudioDevicePropertyDataSources,
+            AudioDevPropSelector::ObjectName => kAudioObjectPropertyName,
+            AudioDevPropSelector::Manufacturer => kAudioObjectPropertyManufacturer,
+            AudioDevPropSelector::DeviceUID => kAudioDevicePropertyDeviceUID,
+            AudioDevPropSelector::ModelUID => kAudioDevicePropertyModelUID,
+            AudioDevPropSelector::NominalSampleRate => kAudioDevicePropertyNominalSampleRate,
+            AudioDevPropSelector::BufferFrameSize => kAudioDevicePropertyBufferFrameSize,
+            AudioDevPropSelector::BufferFrameSizeRange => kAudioDevicePropertyBufferFrameSizeRange,
+            AudioDevPropSelector::Latency => kAudioDevicePropertyLatency,
+            AudioDevPropSelector::SafetyOffset => kAudioDevicePropertySafetyOffset,
+            AudioDevPropSelector::TransportType => kAudioDevicePropertyTransportType,
+            AudioDevPropSelector::IsHidden => kAudioDevicePropertyIsHidden,
+            AudioDevPropSelector::IsAlive => kAudioDevicePropertyDeviceIsAlive,
+            AudioDevPropSelector::IsRunning => kAudioDevicePropertyDeviceIsRunning,
+            AudioDevPropSelector::CanBeDefault => kAudioDevicePropertyDeviceCanBeDefaultDevice,
+            AudioDevPropSelector::PreferredChannelsForStereo => {
+                kAudioDevicePropertyPreferredChannelsForStereo
+            }
+            AudioDevPropSelector::PreferredChannelLayout => {
+                kAudioDevicePropertyPreferredChannelLayout
+            }
+            AudioDevPropSelector::StreamConfiguration => kAudioDevicePropertyStreamConfiguration,
+            AudioDevPropSelector::VolumeDecibels => kAudioDevicePropertyVolumeDecibels,
+            AudioDevPropSelector::VolumeRangeDecibels => kAudioDevicePropertyVolumeRangeDecibels,
+            AudioDevPropSelector::VolumeScalarToDecibels => {
+                kAudioDevicePropertyVolumeScalarToDecibels
+            }
+            AudioDevPropSelector::VolumeDecibelsToScalar => {
+                kAudioDevicePropertyVolumeDecibelsToScalar
+            }
         }
     }
 }
 
+impl TryFrom<u32> for AudioDevPropSelector {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|item| u32::from(*item) == value)
+            .ok_or(Error::UnknownSelector(value))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum AudioHwPropSelector {
     DefaultInputDevice,
     DefaultOutputDevice,
     Devices,
+    TranslateUIDToDevice,
+}
+
+impl AudioHwPropSelector {
+    pub const ALL: [Self; 4] = [
+        Self::DefaultInputDevice,
+        Self::DefaultOutputDevice,
+        Self::Devices,
+        Self::TranslateUIDToDevice,
+    ];
 }
 
 impl From<AudioHwPropSelector> for u32 {
@@ -36,14 +169,88 @@ impl From<AudioHwPropSelector> for u32 {
             AudioHwPropSelector::DefaultInputDevice => kAudioHardwarePropertyDefaultInputDevice,
             AudioHwPropSelector::DefaultOutputDevice => kAudioHardwarePropertyDefaultOutputDevice,
             AudioHwPropSelector::Devices => kAudioHardwarePropertyDevices,
+            AudioHwPropSelector::TranslateUIDToDevice => kAudioHardwarePropertyTranslateUIDToDevice,
         }
     }
 }
 
+impl TryFrom<u32> for AudioHwPropSelector {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|item| u32::from(*item) == value)
+            .ok_or(Error::UnknownSelector(value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AudioStreamPropSelector {
+    Latency,
+    Direction,
+    StartingChannel,
+    TerminalType,
+    IsActive,
+    VirtualFormat,
+    PhysicalFormat,
+    AvailableVirtualFormats,
+    AvailablePhysicalFormats,
+}
+
+impl AudioStreamPropSelector {
+    pub const ALL: [Self; 9] = [
+        Self::Latency,
+        Self::Direction,
+        Self::StartingChannel,
+        Self::TerminalType,
+        Self::IsActive,
+        Self::VirtualFormat,
+        Self::PhysicalFormat,
+        Self::AvailableVirtualFormats,
+        Self::AvailablePhysicalFormats,
+    ];
+}
+
+impl From<AudioStreamPropSelector> for u32 {
+    fn from(value: AudioStreamPropSelector) -> Self {
+        match value {
+            AudioStreamPropSelector::Latency => kAudioStreamPropertyLatency,
+            AudioStreamPropSelector::Direction => kAudioStreamPropertyDirection,
+            AudioStreamPropSelector::StartingChannel => kAudioStreamPropertyStartingChannel,
+            AudioStreamPropSelector::TerminalType => kAudioStreamPropertyTerminalType,
+            AudioStreamPropSelector::IsActive => kAudioStreamPropertyIsActive,
+            AudioStreamPropSelector::VirtualFormat => kAudioStreamPropertyVirtualFormat,
+            AudioStreamPropSelector::PhysicalFormat => kAudioStreamPropertyPhysicalFormat,
+            AudioStreamPropSelector::AvailableVirtualFormats => {
+                kAudioStreamPropertyAvailableVirtualFormats
+            }
+            AudioStreamPropSelector::AvailablePhysicalFormats => {
+                kAudioStreamPropertyAvailablePhysicalFormats
+            }
+        }
+    }
+}
+
+impl TryFrom<u32> for AudioStreamPropSelector {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|item| u32::from(*item) == value)
+            .ok_or(Error::UnknownSelector(value))
+    }
+}
+
+/// Note that some stream selectors share their value with a device selector
+/// (e.g. both latencies are `'ltnc'`); decoding a raw selector prefers the
+/// device one.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum PropertySelector {
     Device(AudioDevPropSelector),
     Hardware(AudioHwPropSelector),
+    Stream(AudioStreamPropSelector),
 }
 
 impl PropertySelector {
@@ -51,11 +258,57 @@ impl PropertySelector {
     pub const DEV_MUTE: Self = Self::Device(AudioDevPropSelector::Mute);
     pub const DEV_NAME: Self = Self::Device(AudioDevPropSelector::Name);
     pub const DEV_STREAMS: Self = Self::Device(AudioDevPropSelector::Streams);
+    pub const DEV_AVAILABLE_NOMINAL_SAMPLE_RATES: Self =
+        Self::Device(AudioDevPropSelector::AvailableNominalSampleRates);
+    pub const DEV_DATA_SOURCE: Self = Self::Device(AudioDevPropSelector::DataSource);
+    pub const DEV_DATA_SOURCES: Self = Self::Device(AudioDevPropSelector::DataSources);
+    pub const DEV_OBJECT_NAME: Self = Self::Device(AudioDevPropSelector::ObjectName);
+    pub const DEV_MANUFACTURER: Self = Self::Device(AudioDevPropSelector::Manufacturer);
+    pub const DEV_UID: Self = Self::Device(AudioDevPropSelector::DeviceUID);
+    pub const DEV_MODEL_UID: Self = Self::Device(AudioDevPropSelector::ModelUID);
+    pub const DEV_NOMINAL_SAMPLE_RATE: Self = Self::Device(AudioDevPropSelector::NominalSampleRate);
+    pub const DEV_BUFFER_FRAME_SIZE: Self = Self::Device(AudioDevPropSelector::BufferFrameSize);
+    pub const DEV_BUFFER_FRAME_SIZE_RANGE: Self =
+        Self::Device(AudioDevPropSelector::BufferFrameSizeRange);
+    pub const DEV_LATENCY: Self = Self::Device(AudioDevPropSelector::Latency);
+    pub const DEV_SAFETY_OFFSET: Self = Self::Device(AudioDevPropSelector::SafetyOffset);
+    pub const DEV_TRANSPORT_TYPE: Self = Self::Device(AudioDevPropSelector::TransportType);
+    pub const DEV_IS_HIDDEN: Self = Self::Device(AudioDevPropSelector::IsHidden);
+    pub const DEV_IS_ALIVE: Self = Self::Device(AudioDevPropSelector::IsAlive);
+    pub const DEV_IS_RUNNING: Self = Self::Device(AudioDevPropSelector::IsRunning);
+    pub const DEV_CAN_BE_DEFAULT: Self = Self::Device(AudioDevPropSelector::CanBeDefault);
+    pub const DEV_PREFERRED_STEREO_CHANNELS: Self =
+        Self::Device(AudioDevPropSelector::PreferredChannelsForStereo);
+    pub const DEV_PREFERRED_CHANNEL_LAYOUT: Self =
+        Self::Device(AudioDevPropSelector::PreferredChannelLayout);
+    pub const DEV_STREAM_CONFIGURATION: Self =
+        Self::Device(AudioDevPropSelector::StreamConfiguration);
+    pub const DEV_VOLUME_DECIBELS: Self = Self::Device(AudioDevPropSelector::VolumeDecibels);
+    pub const DEV_VOLUME_RANGE_DECIBELS: Self =
+        Self::Device(AudioDevPropSelector::VolumeRangeDecibels);
+    pub const DEV_VOLUME_SCALAR_TO_DECIBELS: Self =
+        Self::Device(AudioDevPropSelector::VolumeScalarToDecibels);
+    pub const DEV_VOLUME_DECIBELS_TO_SCALAR: Self =
+        Self::Device(AudioDevPropSelector::VolumeDecibelsToScalar);
 
     pub const HW_DEFAULT_INPUT_DEV: Self = Self::Hardware(AudioHwPropSelector::DefaultInputDevice);
     pub const HW_DEFAULT_OUTPUT_DEV: Self =
         Self::Hardware(AudioHwPropSelector::DefaultOutputDevice);
     pub const HW_ALL_DEVICES: Self = Self::Hardware(AudioHwPropSelector::Devices);
+    pub const HW_TRANSLATE_UID_TO_DEVICE: Self =
+        Self::Hardware(AudioHwPropSelector::TranslateUIDToDevice);
+
+    pub const STM_LATENCY: Self = Self::Stream(AudioStreamPropSelector::Latency);
+    pub const STM_DIRECTION: Self = Self::Stream(AudioStreamPropSelector::Direction);
+    pub const STM_STARTING_CHANNEL: Self = Self::Stream(AudioStreamPropSelector::StartingChannel);
+    pub const STM_TERMINAL_TYPE: Self = Self::Stream(AudioStreamPropSelector::TerminalType);
+    pub const STM_IS_ACTIVE: Self = Self::Stream(AudioStreamPropSelector::IsActive);
+    pub const STM_VIRTUAL_FORMAT: Self = Self::Stream(AudioStreamPropSelector::VirtualFormat);
+    pub const STM_PHYSICAL_FORMAT: Self = Self::Stream(AudioStreamPropSelector::PhysicalFormat);
+    pub const STM_AVAILABLE_VIRTUAL_FORMATS: Self =
+        Self::Stream(AudioStreamPropSelector::AvailableVirtualFormats);
+    pub const STM_AVAILABLE_PHYSICAL_FORMATS: Self =
+        Self::Stream(AudioStreamPropSelector::AvailablePhysicalFormats);
 }
 
 impl From<PropertySelector> for u32 {
@@ -63,6 +316,28 @@ impl From<PropertySelector> for u32 {
         match value {
             PropertySelector::Device(dev) => dev.into(),
             PropertySelector::Hardware(hw) => hw.into(),
+            PropertySelector::Stream(stm) => stm.into(),
+        }
+    }
+}
+
+impl TryFrom<u32> for PropertySelector {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        AudioDevPropSelector::try_from(value)
+            .map(Self::Device)
+            .or_else(|_| AudioHwPropSelector::try_from(value).map(Self::Hardware))
+            .or_else(|_| AudioStreamPropSelector::try_from(value).map(Self::Stream))
+    }
+}
+
+impl Display for PropertySelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Device(dev) => write!(f, "{dev:?}"),
+            Self::Hardware(hw) => write!(f, "{hw:?}"),
+            Self::Stream(stm) => write!(f, "{stm:?}"),
         }
     }
 }
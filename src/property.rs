@@ -0,0 +1,306 @@
+//! Typed property values.
+//!
+//! [`PropertyValue`] describes how a Rust type is read from and written to the
+//! HAL, and [`TypedSelector`] binds a selector to the type its data has, so that
+//! reading a property with the wrong type doesn't compile.
+
+pub use crate::sys::{AudioStreamBasicDescription, AudioStreamRangedDescription, AudioValueRange};
+use crate::{
+    aopa::AudioObjPropAddress,
+    error::{Error, HalError, HalErrorKind},
+    hal::HalBackend,
+    mscope::PropertyScope,
+    mselector::PropertySelector,
+    sys::{
+        kAudioHardwareBadPropertySizeError, AudioDeviceID, AudioObjectID, AudioStreamID, OSStatus,
+    },
+};
+use std::{
+    marker::PhantomData,
+    mem::{size_of, size_of_val},
+    slice,
+};
+
+const ARRAY_READ_ATTEMPTS: usize = 3;
+
+/// A value that can be stored in a HAL property.
+pub trait PropertyValue: Sized {
+    fn read(
+        backend: &dyn HalBackend,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<Self, Error>;
+
+    fn write(
+        &self,
+        backend: &dyn HalBackend,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<(), Error>;
+}
+
+/// Types whose HAL representation is their in-memory representation.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` (or primitive), contain no padding and no
+/// pointers, and be valid for any bit pattern.
+pub unsafe trait PlainData: Copy + Default {}
+
+unsafe impl PlainData for u8 {}
+unsafe impl PlainData for u32 {}
+unsafe impl PlainData for i32 {}
+unsafe impl PlainData for f32 {}
+unsafe impl PlainData for f64 {}
+unsafe impl PlainData for AudioValueRange {}
+unsafe impl PlainData for AudioStreamBasicDescription {}
+unsafe impl PlainData for AudioStreamRangedDescription {}
+
+pub(crate) fn as_bytes<T: PlainData>(items: &[T]) -> &[u8] {
+    unsafe { slice::from_raw_parts(items.as_ptr().cast(), size_of_val(items)) }
+}
+
+pub(crate) fn as_bytes_mut<T: PlainData>(items: &mut [T]) -> &mut [u8] {
+    unsafe { slice::from_raw_parts_mut(items.as_mut_ptr().cast(), size_of_val(items)) }
+}
+
+impl<T: PlainData> PropertyValue for T {
+    fn read(
+        backend: &dyn HalBackend,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<Self, Error> {
+        let mut value = T::default();
+        backend.get_property_data(id, address, as_bytes_mut(slice::from_mut(&mut value)))?;
+
+        Ok(value)
+    }
+
+    fn write(
+        &self,
+        backend: &dyn HalBackend,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<(), Error> {
+        backend.set_property_data(id, address, as_bytes(slice::from_ref(self)))
+    }
+}
+
+impl<T: PlainData> PropertyValue for Vec<T> {
+    fn read(
+        backend: &dyn HalBackend,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<Self, Error> {
+        read_array(backend, id, address)
+    }
+
+    fn write(
+        &self,
+        backend: &dyn HalBackend,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<(), Error> {
+        backend.set_property_data(id, address, as_bytes(self))
+    }
+}
+
+/// Reads a variable-length array property.
+///
+/// The list may change between querying its size and reading it, e.g. when a
+/// device is plugged in. If the HAL then rejects the buffer size, or the buffer
+/// came back full and the list has since grown, the read is retried with a
+/// freshly queried size. A list that is still growing after the last attempt is
+/// reported as `BadPropertySize` rather than returned incomplete.
+pub(crate) fn read_array<T: PlainData>(
+    backend: &dyn HalBackend,
+    id: AudioObjectID,
+    address: AudioObjPropAddress,
+) -> Result<Vec<T>, Error> {
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let size = backend.get_property_data_size(id, address)?;
+        let mut items = vec![T::default(); size / size_of::<T>()];
+
+        match backend.get_property_data(id, address, as_bytes_mut(&mut items)) {
+            Ok(written) if written == size => {
+                if backend.get_property_data_size(id, address)? <= size {
+                    return Ok(items);
+                }
+
+                if attempt == ARRAY_READ_ATTEMPTS {
+                    return Err(HalError::for_property(
+                        kAudioHardwareBadPropertySizeError as OSStatus,
+                        id,
+                        address,
+                    )
+                    .into());
+                }
+            }
+            Ok(written) => {
+                items.truncate(written / size_of::<T>());
+                return Ok(items);
+            }
+            Err(Error::Hal(err))
+                if err.kind() == HalErrorKind::BadPropertySize && attempt < ARRAY_READ_ATTEMPTS => {
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Boolean properties are `UInt32`s in the HAL.
+impl PropertyValue for bool {
+    fn read(
+        backend: &dyn HalBackend,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<Self, Error> {
+        u32::read(backend, id, address).map(|value| value != 0)
+    }
+
+    fn write(
+        &self,
+        backend: &dyn HalBackend,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<(), Error> {
+        u32::from(*self).write(backend, id, address)
+    }
+}
+
+/// `CFString` properties.
+impl PropertyValue for String {
+    fn read(
+        backend: &dyn HalBackend,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<Self, Error> {
+        backend.get_string_property(id, address)
+    }
+
+    fn write(
+        &self,
+        backend: &dyn HalBackend,
+        id: AudioObjectID,
+        address: AudioObjPropAddress,
+    ) -> Result<(), Error> {
+        backend.set_string_property(id, address, self)
+    }
+}
+
+/// A property selector bound to the type of its value.
+pub struct TypedSelector<T> {
+    selector: PropertySelector,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedSelector<T> {
+    pub const fn new(selector: PropertySelector) -> Self {
+        Self {
+            selector,
+            _value: PhantomData,
+        }
+    }
+
+    pub const fn selector(&self) -> PropertySelector {
+        self.selector
+    }
+
+    pub const fn address(&self, scope: PropertyScope, element: u32) -> AudioObjPropAddress {
+        AudioObjPropAddress::new_with_element(self.selector, scope, element)
+    }
+}
+
+impl<T> Clone for TypedSelector<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TypedSelector<T> {}
+
+impl<T> std::fmt::Debug for TypedSelector<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TypedSelector")
+            .field(&self.selector)
+            .finish()
+    }
+}
+
+pub const VOLUME_SCALAR: TypedSelector<f32> =
+    TypedSelector::new(PropertySelector::DEV_VOLUME_SCALAR);
+pub const VOLUME_DECIBELS: TypedSelector<f32> =
+    TypedSelector::new(PropertySelector::DEV_VOLUME_DECIBELS);
+pub const VOLUME_RANGE_DECIBELS: TypedSelector<AudioValueRange> =
+    TypedSelector::new(PropertySelector::DEV_VOLUME_RANGE_DECIBELS);
+/// Translation property: reading it converts the `f32` scalar passed in.
+pub const VOLUME_SCALAR_TO_DECIBELS: TypedSelector<f32> =
+    TypedSelector::new(PropertySelector::DEV_VOLUME_SCALAR_TO_DECIBELS);
+/// Translation property: reading it converts the `f32` decibel value passed in.
+pub const VOLUME_DECIBELS_TO_SCALAR: TypedSelector<f32> =
+    TypedSelector::new(PropertySelector::DEV_VOLUME_DECIBELS_TO_SCALAR);
+pub const MUTE: TypedSelector<bool> = TypedSelector::new(PropertySelector::DEV_MUTE);
+pub const NAME: TypedSelector<String> = TypedSelector::new(PropertySelector::DEV_OBJECT_NAME);
+pub const MANUFACTURER: TypedSelector<String> =
+    TypedSelector::new(PropertySelector::DEV_MANUFACTURER);
+pub const DEVICE_UID: TypedSelector<String> = TypedSelector::new(PropertySelector::DEV_UID);
+pub const MODEL_UID: TypedSelector<String> = TypedSelector::new(PropertySelector::DEV_MODEL_UID);
+pub const STREAMS: TypedSelector<Vec<AudioStreamID>> =
+    TypedSelector::new(PropertySelector::DEV_STREAMS);
+pub const NOMINAL_SAMPLE_RATE: TypedSelector<f64> =
+    TypedSelector::new(PropertySelector::DEV_NOMINAL_SAMPLE_RATE);
+pub const AVAILABLE_NOMINAL_SAMPLE_RATES: TypedSelector<Vec<AudioValueRange>> =
+    TypedSelector::new(PropertySelector::DEV_AVAILABLE_NOMINAL_SAMPLE_RATES);
+pub const BUFFER_FRAME_SIZE: TypedSelector<u32> =
+    TypedSelector::new(PropertySelector::DEV_BUFFER_FRAME_SIZE);
+pub const BUFFER_FRAME_SIZE_RANGE: TypedSelector<AudioValueRange> =
+    TypedSelector::new(PropertySelector::DEV_BUFFER_FRAME_SIZE_RANGE);
+pub const LATENCY: TypedSelector<u32> = TypedSelector::new(PropertySelector::DEV_LATENCY);
+pub const SAFETY_OFFSET: TypedSelector<u32> =
+    TypedSelector::new(PropertySelector::DEV_SAFETY_OFFSET);
+pub const TRANSPORT_TYPE: TypedSelector<u32> =
+    TypedSelector::new(PropertySelector::DEV_TRANSPORT_TYPE);
+pub const IS_HIDDEN: TypedSelector<bool> = TypedSelector::new(PropertySelector::DEV_IS_HIDDEN);
+pub const IS_ALIVE: TypedSelector<bool> = TypedSelector::new(PropertySelector::DEV_IS_ALIVE);
+pub const IS_RUNNING: TypedSelector<bool> = TypedSelector::new(PropertySelector::DEV_IS_RUNNING);
+pub const CAN_BE_DEFAULT: TypedSelector<bool> =
+    TypedSelector::new(PropertySelector::DEV_CAN_BE_DEFAULT);
+pub const PREFERRED_STEREO_CHANNELS: TypedSelector<Vec<u32>> =
+    TypedSelector::new(PropertySelector::DEV_PREFERRED_STEREO_CHANNELS);
+/// A variable-length `AudioChannelLayout`, see [`ChannelLayout`](crate::devices::ChannelLayout).
+pub const PREFERRED_CHANNEL_LAYOUT: TypedSelector<Vec<u8>> =
+    TypedSelector::new(PropertySelector::DEV_PREFERRED_CHANNEL_LAYOUT);
+/// A variable-length `AudioBufferList` describing the channels of each stream.
+pub const STREAM_CONFIGURATION: TypedSelector<Vec<u8>> =
+    TypedSelector::new(PropertySelector::DEV_STREAM_CONFIGURATION);
+pub const DATA_SOURCE: TypedSelector<u32> = TypedSelector::new(PropertySelector::DEV_DATA_SOURCE);
+pub const DATA_SOURCES: TypedSelector<Vec<u32>> =
+    TypedSelector::new(PropertySelector::DEV_DATA_SOURCES);
+
+pub const DEVICES: TypedSelector<Vec<AudioDeviceID>> =
+    TypedSelector::new(PropertySelector::HW_ALL_DEVICES);
+pub const DEFAULT_INPUT_DEVICE: TypedSelector<AudioDeviceID> =
+    TypedSelector::new(PropertySelector::HW_DEFAULT_INPUT_DEV);
+pub const DEFAULT_OUTPUT_DEVICE: TypedSelector<AudioDeviceID> =
+    TypedSelector::new(PropertySelector::HW_DEFAULT_OUTPUT_DEV);
+
+pub const STREAM_LATENCY: TypedSelector<u32> = TypedSelector::new(PropertySelector::STM_LATENCY);
+pub const STREAM_DIRECTION: TypedSelector<u32> =
+    TypedSelector::new(PropertySelector::STM_DIRECTION);
+pub const STARTING_CHANNEL: TypedSelector<u32> =
+    TypedSelector::new(PropertySelector::STM_STARTING_CHANNEL);
+pub const TERMINAL_TYPE: TypedSelector<u32> =
+    TypedSelector::new(PropertySelector::STM_TERMINAL_TYPE);
+pub const IS_ACTIVE: TypedSelector<bool> = TypedSelector::new(PropertySelector::STM_IS_ACTIVE);
+pub const VIRTUAL_FORMAT: TypedSelector<AudioStreamBasicDescription> =
+    TypedSelector::new(PropertySelector::STM_VIRTUAL_FORMAT);
+pub const PHYSICAL_FORMAT: TypedSelector<AudioStreamBasicDescription> =
+    TypedSelector::new(PropertySelector::STM_PHYSICAL_FORMAT);
+pub const AVAILABLE_VIRTUAL_FORMATS: TypedSelector<Vec<AudioStreamRangedDescription>> =
+    TypedSelector::new(PropertySelector::STM_AVAILABLE_VIRTUAL_FORMATS);
+pub const AVAILABLE_PHYSICAL_FORMATS: TypedSelector<Vec<AudioStreamRangedDescription>> =
+    TypedSelector::new(PropertySelector::STM_AVAILABLE_PHYSICAL_FORMATS);
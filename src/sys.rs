@@ -0,0 +1,217 @@
+//! Raw CoreAudio bindings.
+//!
+//! On macOS this simply re-exports `coreaudio-sys`. Everywhere else it provides
+//! the small subset of types and constants this crate needs, so that the
+//! high-level API can be built and tested against a simulated HAL.
+
+#[cfg(target_os = "macos")]
+pub use coreaudio_sys::*;
+
+#[cfg(not(target_os = "macos"))]
+pub use self::portable::*;
+
+#[cfg(not(target_os = "macos"))]
+#[allow(
+    dead_code,
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals
+)]
+mod portable {
+    const fn fourcc(code: &[u8; 4]) -> u32 {
+        u32::from_be_bytes(*code)
+    }
+
+    pub type UInt32 = u32;
+    pub type Float64 = f64;
+    pub type OSStatus = i32;
+    pub type AudioObjectID = u32;
+    pub type AudioDeviceID = AudioObjectID;
+    pub type AudioStreamID = AudioObjectID;
+    pub type AudioObjectPropertySelector = u32;
+    pub type AudioObjectPropertyScope = u32;
+    pub type AudioObjectPropertyElement = u32;
+
+    #[repr(C)]
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct AudioObjectPropertyAddress {
+        pub mSelector: AudioObjectPropertySelector,
+        pub mScope: AudioObjectPropertyScope,
+        pub mElement: AudioObjectPropertyElement,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct AudioValueRange {
+        pub mMinimum: Float64,
+        pub mMaximum: Float64,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct AudioStreamBasicDescription {
+        pub mSampleRate: Float64,
+        pub mFormatID: UInt32,
+        pub mFormatFlags: UInt32,
+        pub mBytesPerPacket: UInt32,
+        pub mFramesPerPacket: UInt32,
+        pub mBytesPerFrame: UInt32,
+        pub mChannelsPerFrame: UInt32,
+        pub mBitsPerChannel: UInt32,
+        pub mReserved: UInt32,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct AudioStreamRangedDescription {
+        pub mFormat: AudioStreamBasicDescription,
+        pub mSampleRateRange: AudioValueRange,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct AudioBuffer {
+        pub mNumberChannels: UInt32,
+        pub mDataByteSize: UInt32,
+        pub mData: *mut std::ffi::c_void,
+    }
+
+    pub const kAudioObjectUnknown: AudioObjectID = 0;
+    pub const kAudioObjectSystemObject: AudioObjectID = 1;
+
+    pub const kAudioObjectPropertyScopeGlobal: u32 = fourcc(b"glob");
+    pub const kAudioDevicePropertyScopeInput: u32 = fourcc(b"inpt");
+    pub const kAudioDevicePropertyScopeOutput: u32 = fourcc(b"outp");
+    pub const kAudioDevicePropertyScopePlayThrough: u32 = fourcc(b"ptru");
+
+    pub const kAudioHardwarePropertyDevices: u32 = fourcc(b"dev#");
+    pub const kAudioHardwarePropertyDefaultInputDevice: u32 = fourcc(b"dIn ");
+    pub const kAudioHardwarePropertyDefaultOutputDevice: u32 = fourcc(b"dOut");
+    pub const kAudioHardwarePropertyTranslateUIDToDevice: u32 = fourcc(b"uidd");
+
+    pub const kAudioDevicePropertyDeviceName: u32 = fourcc(b"name");
+    pub const kAudioDevicePropertyStreams: u32 = fourcc(b"stm#");
+    pub const kAudioDevicePropertyVolumeScalar: u32 = fourcc(b"volm");
+    pub const kAudioDevicePropertyMute: u32 = fourcc(b"mute");
+    pub const kAudioDevicePropertyAvailableNominalSampleRates: u32 = fourcc(b"nsr#");
+    pub const kAudioDevicePropertyDataSource: u32 = fourcc(b"ssrc");
+    pub const kAudioDevicePropertyDataSources: u32 = fourcc(b"dsc#");
+    pub const kAudioObjectPropertyName: u32 = fourcc(b"lnam");
+    pub const kAudioObjectPropertyManufacturer: u32 = fourcc(b"lmak");
+    pub const kAudioDevicePropertyDeviceUID: u32 = fourcc(b"uid ");
+    pub const kAudioDevicePropertyModelUID: u32 = fourcc(b"muid");
+    pub const kAudioDevicePropertyNominalSampleRate: u32 = fourcc(b"nsrt");
+    pub const kAudioDevicePropertyBufferFrameSize: u32 = fourcc(b"fsiz");
+    pub const kAudioDevicePropertyBufferFrameSizeRange: u32 = fourcc(b"fsz#");
+    pub const kAudioDevicePropertyLatency: u32 = fourcc(b"ltnc");
+    pub const kAudioDevicePropertySafetyOffset: u32 = fourcc(b"saft");
+    pub const kAudioDevicePropertyTransportType: u32 = fourcc(b"tran");
+    pub const kAudioDevicePropertyIsHidden: u32 = fourcc(b"hidn");
+    pub const kAudioDevicePropertyDeviceIsAlive: u32 = fourcc(b"livn");
+    pub const kAudioDevicePropertyDeviceIsRunning: u32 = fourcc(b"goin");
+    pub const kAudioDevicePropertyDeviceCanBeDefaultDevice: u32 = fourcc(b"dflt");
+
+    pub const kAudioDeviceTransportTypeUnknown: u32 = 0;
+    pub const kAudioDeviceTransportTypeBuiltIn: u32 = fourcc(b"bltn");
+    pub const kAudioDeviceTransportTypeAggregate: u32 = fourcc(b"grup");
+    pub const kAudioDeviceTransportTypeAutoAggregate: u32 = fourcc(b"fgrp");
+    pub const kAudioDeviceTransportTypeVirtual: u32 = fourcc(b"virt");
+    pub const kAudioDeviceTransportTypePCI: u32 = fourcc(b"pci ");
+    pub const kAudioDeviceTransportTypeUSB: u32 = fourcc(b"usb ");
+    pub const kAudioDeviceTransportTypeFireWire: u32 = fourcc(b"1394");
+    pub const kAudioDeviceTransportTypeBluetooth: u32 = fourcc(b"blue");
+    pub const kAudioDeviceTransportTypeBluetoothLE: u32 = fourcc(b"blea");
+    pub const kAudioDeviceTransportTypeHDMI: u32 = fourcc(b"hdmi");
+    pub const kAudioDeviceTransportTypeDisplayPort: u32 = fourcc(b"dprt");
+    pub const kAudioDeviceTransportTypeAirPlay: u32 = fourcc(b"airp");
+    pub const kAudioDeviceTransportTypeAVB: u32 = fourcc(b"eavb");
+    pub const kAudioDeviceTransportTypeThunderbolt: u32 = fourcc(b"thun");
+    pub const kAudioDevicePropertyPreferredChannelsForStereo: u32 = fourcc(b"dch2");
+    pub const kAudioDevicePropertyPreferredChannelLayout: u32 = fourcc(b"srnd");
+    pub const kAudioDevicePropertyStreamConfiguration: u32 = fourcc(b"slay");
+    pub const kAudioDevicePropertyVolumeDecibels: u32 = fourcc(b"vold");
+    pub const kAudioDevicePropertyVolumeRangeDecibels: u32 = fourcc(b"vdb#");
+    pub const kAudioDevicePropertyVolumeScalarToDecibels: u32 = fourcc(b"v2db");
+    pub const kAudioDevicePropertyVolumeDecibelsToScalar: u32 = fourcc(b"db2v");
+
+    pub const kAudioStreamPropertyLatency: u32 = fourcc(b"ltnc");
+    pub const kAudioStreamPropertyDirection: u32 = fourcc(b"sdir");
+    pub const kAudioStreamPropertyStartingChannel: u32 = fourcc(b"schn");
+    pub const kAudioStreamPropertyTerminalType: u32 = fourcc(b"term");
+    pub const kAudioStreamPropertyIsActive: u32 = fourcc(b"sact");
+    pub const kAudioStreamPropertyVirtualFormat: u32 = fourcc(b"sfmt");
+    pub const kAudioStreamPropertyPhysicalFormat: u32 = fourcc(b"pft ");
+
+    pub const kAudioStreamTerminalTypeUnknown: u32 = 0;
+    pub const kAudioStreamTerminalTypeLine: u32 = fourcc(b"line");
+    pub const kAudioStreamTerminalTypeDigitalAudioInterface: u32 = fourcc(b"spdf");
+    pub const kAudioStreamTerminalTypeSpeaker: u32 = fourcc(b"spkr");
+    pub const kAudioStreamTerminalTypeHeadphones: u32 = fourcc(b"hdph");
+    pub const kAudioStreamTerminalTypeLFESpeaker: u32 = fourcc(b"lfes");
+    pub const kAudioStreamTerminalTypeReceiverSpeaker: u32 = fourcc(b"rspk");
+    pub const kAudioStreamTerminalTypeMicrophone: u32 = fourcc(b"micr");
+    pub const kAudioStreamTerminalTypeHeadsetMicrophone: u32 = fourcc(b"hmic");
+    pub const kAudioStreamTerminalTypeReceiverMicrophone: u32 = fourcc(b"rmic");
+    pub const kAudioStreamTerminalTypeTTY: u32 = fourcc(b"tty_");
+    pub const kAudioStreamTerminalTypeHDMI: u32 = fourcc(b"hdmi");
+    pub const kAudioStreamTerminalTypeDisplayPort: u32 = fourcc(b"dprt");
+
+    pub const kAudioFormatLinearPCM: u32 = fourcc(b"lpcm");
+    pub const kAudioFormatAC3: u32 = fourcc(b"ac-3");
+    pub const kAudioFormat60958AC3: u32 = fourcc(b"cac3");
+    pub const kAudioFormatFlagIsFloat: u32 = 1 << 0;
+    pub const kAudioFormatFlagIsBigEndian: u32 = 1 << 1;
+    pub const kAudioFormatFlagIsSignedInteger: u32 = 1 << 2;
+    pub const kAudioFormatFlagIsPacked: u32 = 1 << 3;
+    pub const kAudioFormatFlagIsAlignedHigh: u32 = 1 << 4;
+    pub const kAudioFormatFlagIsNonInterleaved: u32 = 1 << 5;
+    pub const kAudioFormatFlagIsNonMixable: u32 = 1 << 6;
+    pub const kAudioStreamPropertyAvailableVirtualFormats: u32 = fourcc(b"sfm#");
+    pub const kAudioStreamPropertyAvailablePhysicalFormats: u32 = fourcc(b"pft#");
+
+    pub const kAudioChannelLabel_Unknown: u32 = 0xFFFF_FFFF;
+    pub const kAudioChannelLabel_Unused: u32 = 0;
+    pub const kAudioChannelLabel_UseCoordinates: u32 = 100;
+    pub const kAudioChannelLabel_Left: u32 = 1;
+    pub const kAudioChannelLabel_Right: u32 = 2;
+    pub const kAudioChannelLabel_Center: u32 = 3;
+    pub const kAudioChannelLabel_LFEScreen: u32 = 4;
+    pub const kAudioChannelLabel_LeftSurround: u32 = 5;
+    pub const kAudioChannelLabel_RightSurround: u32 = 6;
+    pub const kAudioChannelLabel_LeftCenter: u32 = 7;
+    pub const kAudioChannelLabel_RightCenter: u32 = 8;
+    pub const kAudioChannelLabel_CenterSurround: u32 = 9;
+    pub const kAudioChannelLabel_LeftSurroundDirect: u32 = 10;
+    pub const kAudioChannelLabel_RightSurroundDirect: u32 = 11;
+    pub const kAudioChannelLabel_TopCenterSurround: u32 = 12;
+    pub const kAudioChannelLabel_VerticalHeightLeft: u32 = 13;
+    pub const kAudioChannelLabel_VerticalHeightCenter: u32 = 14;
+    pub const kAudioChannelLabel_VerticalHeightRight: u32 = 15;
+    pub const kAudioChannelLabel_TopBackLeft: u32 = 16;
+    pub const kAudioChannelLabel_TopBackCenter: u32 = 17;
+    pub const kAudioChannelLabel_TopBackRight: u32 = 18;
+    pub const kAudioChannelLabel_RearSurroundLeft: u32 = 33;
+    pub const kAudioChannelLabel_RearSurroundRight: u32 = 34;
+    pub const kAudioChannelLabel_LeftWide: u32 = 35;
+    pub const kAudioChannelLabel_RightWide: u32 = 36;
+    pub const kAudioChannelLabel_LFE2: u32 = 37;
+    pub const kAudioChannelLabel_Mono: u32 = 42;
+    pub const kAudioChannelLabel_Discrete_0: u32 = 1 << 16;
+
+    pub const kAudioChannelLayoutTag_UseChannelDescriptions: u32 = 0;
+    pub const kAudioChannelLayoutTag_UseChannelBitmap: u32 = 1 << 16;
+
+    pub const kAudioHardwareNoError: u32 = 0;
+    pub const kAudioHardwareUnspecifiedError: u32 = fourcc(b"what");
+    pub const kAudioHardwareUnknownPropertyError: u32 = fourcc(b"who?");
+    pub const kAudioHardwareBadPropertySizeError: u32 = fourcc(b"!siz");
+    pub const kAudioHardwareIllegalOperationError: u32 = fourcc(b"nope");
+    pub const kAudioHardwareBadObjectError: u32 = fourcc(b"!obj");
+    pub const kAudioHardwareNotRunningError: u32 = fourcc(b"stop");
+    pub const kAudioHardwareBadDeviceError: u32 = fourcc(b"!dev");
+    pub const kAudioHardwareBadStreamError: u32 = fourcc(b"!str");
+    pub const kAudioHardwareUnsupportedOperationError: u32 = fourcc(b"unop");
+    pub const kAudioHardwareNotReadyError: u32 = fourcc(b"nrdy");
+    pub const kAudioDeviceUnsupportedFormatError: u32 = fourcc(b"!dat");
+    pub const kAudioDevicePermissionsError: u32 = fourcc(b"!hog");
+}
@@ -0,0 +1,18 @@
+use coreaudio_hl::error::{Error, HalError, HalErrorKind};
+
+#[test]
+fn known_status_codes_are_named() {
+    let err = HalError::new(i32::from_be_bytes(*b"who?"));
+
+    assert_eq!(err.kind(), HalErrorKind::UnknownProperty);
+    assert_eq!(err.to_string(), "unknown property ('who?')");
+}
+
+#[test]
+fn unknown_status_codes_are_rendered() {
+    let fourcc = Error::from(i32::from_be_bytes(*b"abcd"));
+    let numeric = Error::from(-50);
+
+    assert_eq!(fourcc.to_string(), "OS error ('abcd')");
+    assert_eq!(numeric.to_string(), "OS error (-50)");
+}
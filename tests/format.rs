@@ -0,0 +1,110 @@
+use coreaudio_hl::{
+    devices::{FormatFlag, FormatFlags, FormatId, RangedStreamFormat, StreamFormat},
+    property::{AudioStreamBasicDescription, AudioStreamRangedDescription, AudioValueRange},
+};
+
+fn asbd_fields(asbd: &AudioStreamBasicDescription) -> (f64, [u32; 7]) {
+    (
+        asbd.mSampleRate,
+        [
+            asbd.mFormatID,
+            asbd.mFormatFlags,
+            asbd.mBytesPerPacket,
+            asbd.mFramesPerPacket,
+            asbd.mBytesPerFrame,
+            asbd.mChannelsPerFrame,
+            asbd.mBitsPerChannel,
+        ],
+    )
+}
+
+#[test]
+fn basic_description_round_trip() {
+    // 24-bit signed integer in the high bits of 32-bit words, non-interleaved,
+    // with 8 fractional bits encoded in the format-specific flag bits.
+    let asbd = AudioStreamBasicDescription {
+        mSampleRate: 96_000.0,
+        mFormatID: u32::from_be_bytes(*b"lpcm"),
+        mFormatFlags: 4 | 16 | 32 | (8 << 7),
+        mBytesPerPacket: 4,
+        mFramesPerPacket: 1,
+        mBytesPerFrame: 4,
+        mChannelsPerFrame: 6,
+        mBitsPerChannel: 24,
+        mReserved: 0,
+    };
+
+    let format = StreamFormat::from(asbd);
+    assert_eq!(format.format_id, FormatId::LinearPcm);
+    assert_eq!(
+        format.flags.iter().collect::<Vec<_>>(),
+        [
+            FormatFlag::SignedInteger,
+            FormatFlag::AlignedHigh,
+            FormatFlag::NonInterleaved
+        ]
+    );
+    assert!(!format.is_interleaved());
+    assert!(!format.is_float());
+    assert!(format.is_mixable());
+
+    let back = AudioStreamBasicDescription::from(format);
+    assert_eq!(asbd_fields(&back), asbd_fields(&asbd));
+}
+
+#[test]
+fn unknown_format_ids_are_preserved() {
+    let asbd = AudioStreamBasicDescription {
+        mSampleRate: 48_000.0,
+        mFormatID: u32::from_be_bytes(*b"ec-3"),
+        mFramesPerPacket: 1536,
+        mChannelsPerFrame: 2,
+        ..Default::default()
+    };
+
+    let format = StreamFormat::from(asbd);
+    assert_eq!(
+        format.format_id,
+        FormatId::Other(u32::from_be_bytes(*b"ec-3"))
+    );
+    assert_eq!(
+        asbd_fields(&AudioStreamBasicDescription::from(format)),
+        asbd_fields(&asbd)
+    );
+}
+
+#[test]
+fn linear_pcm_layout() {
+    let interleaved = StreamFormat::linear_pcm(44_100.0, 2, 16, false, true);
+    assert_eq!(interleaved.bytes_per_frame, 4);
+    assert_eq!(interleaved.bytes_per_packet, 4);
+    assert_eq!(
+        interleaved.flags,
+        [FormatFlag::SignedInteger, FormatFlag::Packed]
+            .into_iter()
+            .collect::<FormatFlags>()
+    );
+
+    let planar = StreamFormat::linear_pcm(44_100.0, 2, 32, true, false);
+    assert_eq!(planar.bytes_per_frame, 4);
+    assert!(!planar.is_interleaved());
+    assert_eq!(planar.flags.raw(), 1 | 8 | 32);
+}
+
+#[test]
+fn ranged_description() {
+    let ranged = RangedStreamFormat::from(AudioStreamRangedDescription {
+        mFormat: StreamFormat::linear_pcm(0.0, 2, 24, false, true).into(),
+        mSampleRateRange: AudioValueRange {
+            mMinimum: 44_100.0,
+            mMaximum: 192_000.0,
+        },
+    });
+
+    assert_eq!(ranged.sample_rates, 44_100.0..=192_000.0);
+    assert_eq!(
+        ranged.at_sample_rate(88_200.0).unwrap().sample_rate,
+        88_200.0
+    );
+    assert!(ranged.at_sample_rate(32_000.0).is_none());
+}
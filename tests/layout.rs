@@ -0,0 +1,69 @@
+use coreaudio_hl::{
+    devices::{ChannelLabel, ChannelLayout},
+    error::Error,
+};
+
+fn words(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_ne_bytes()).collect()
+}
+
+#[test]
+fn descriptions_are_decoded() {
+    // Tag, bitmap, count, then label, flags and three coordinates per channel.
+    let data = words(&[0, 0, 3, 1, 0, 0, 0, 0, 2, 0, 0, 0, 0, 4, 0, 0, 0, 0]);
+    let layout = ChannelLayout::from_bytes(&data).unwrap();
+
+    assert_eq!(
+        layout.channels,
+        [
+            ChannelLabel::Left,
+            ChannelLabel::Right,
+            ChannelLabel::LfeScreen
+        ]
+    );
+    assert_eq!(layout.label(3), Some(ChannelLabel::LfeScreen));
+    assert_eq!(layout.label(0), None);
+    assert_eq!(layout.element(ChannelLabel::Right), Some(2));
+    assert_eq!(layout.to_bytes(), data);
+}
+
+#[test]
+fn bitmap_layouts_are_decoded() {
+    // L, R, C, LFE, Ls, Rs
+    let data = words(&[1 << 16, 0b11_1111, 0]);
+    let layout = ChannelLayout::from_bytes(&data).unwrap();
+
+    assert_eq!(
+        layout.channels,
+        [
+            ChannelLabel::Left,
+            ChannelLabel::Right,
+            ChannelLabel::Center,
+            ChannelLabel::LfeScreen,
+            ChannelLabel::LeftSurround,
+            ChannelLabel::RightSurround
+        ]
+    );
+}
+
+#[test]
+fn labels_round_trip() {
+    assert_eq!(ChannelLabel::from_raw(0x1_0003), ChannelLabel::Discrete(3));
+    assert_eq!(ChannelLabel::Discrete(3).raw(), 0x1_0003);
+    assert_eq!(ChannelLabel::from_raw(0xFFFF_FFFF), ChannelLabel::Unknown);
+    assert_eq!(ChannelLabel::from_raw(500), ChannelLabel::Other(500));
+}
+
+#[test]
+fn truncated_layouts_are_rejected() {
+    let data = words(&[0, 0, 2, 1, 0, 0, 0, 0]);
+
+    assert!(matches!(
+        ChannelLayout::from_bytes(&data),
+        Err(Error::InvalidChannelLayout)
+    ));
+    assert!(matches!(
+        ChannelLayout::from_bytes(&data[..6]),
+        Err(Error::InvalidChannelLayout)
+    ));
+}
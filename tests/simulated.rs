@@ -0,0 +1,1194 @@
+use coreaudio_hl::{
+    aopa::AudioObjPropAddress,
+    devices::{
+        self, get_all_devices, AudioDevice, AudioOutputDevice, AudioStream, ChannelLabel,
+        DeviceScope, FormatFlag, FormatId, Latency, ScopedDevice, StreamDirection, StreamFormat,
+        TerminalType, TransportType, VolumeControlKind, VolumeMode,
+    },
+    error::{Error, HalErrorKind},
+    events::{
+        devices_changed::{self, DeviceListEvent},
+        listener::PropertyListener,
+        output_changed,
+    },
+    hal::{self, ReadFault, SimDevice, SimulatedHal},
+    mscope::PropertyScope,
+    mselector::PropertySelector,
+    property,
+};
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+#[test]
+fn device_from_id() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Speakers").output_channels(2));
+
+    let device = AudioDevice::from_id(id).unwrap();
+
+    assert_eq!(device.id(), id);
+    assert_eq!(device.name(), "Speakers");
+    assert_eq!(device.output_channels(), &[1, 2]);
+    assert!(device.is_output());
+    assert!(!device.is_input());
+    assert_eq!(device.output_streams(), 1);
+    assert_eq!(device.input_streams(), 0);
+    assert_eq!(get_all_devices().unwrap().len(), 1);
+}
+
+#[test]
+fn channels_without_volume_controls() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Interface")
+            .input_channels(2)
+            .output_channels(8)
+            .channel_volume(false),
+    );
+    for channel in [1, 2, 7] {
+        sim.define_property(
+            id,
+            AudioObjPropAddress::new_with_element(
+                PropertySelector::DEV_VOLUME_SCALAR,
+                PropertyScope::DEV_OUTPUT,
+                channel,
+            ),
+            0.5f32.to_ne_bytes(),
+            true,
+        );
+    }
+
+    let device = AudioDevice::from_id(id).unwrap();
+
+    assert!(device.is_multi());
+    assert_eq!(device.input_channels(), &[1, 2]);
+    assert_eq!(device.output_channels(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(device.controllable_channels(PropertyScope::DEV_INPUT), [0]);
+    assert_eq!(
+        device.controllable_channels(PropertyScope::DEV_OUTPUT),
+        [0, 1, 2, 7]
+    );
+    assert_eq!(device.as_output().unwrap().avg_volume().unwrap(), 0.5);
+}
+
+#[test]
+fn output_volume_and_mute() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Headphones").output_channels(2).volume(0.25));
+    sim.set_default_output(id);
+
+    let device = AudioDevice::default_output().unwrap();
+    assert_eq!(device.avg_volume().unwrap(), 0.25);
+
+    device.set_volume(0.75).unwrap();
+    assert_eq!(device.volume_for_channel(1).unwrap(), 0.75);
+    assert_eq!(device.volume_for_channel(2).unwrap(), 0.75);
+    assert!(device.set_volume(1.5).is_err());
+
+    assert!(!device.muted().unwrap());
+    device.set_mute(true).unwrap();
+    assert!(device.muted().unwrap());
+}
+
+#[test]
+fn input_gain_and_mute() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Headset")
+            .input_channels(1)
+            .output_channels(2)
+            .volume(0.25),
+    );
+    sim.set_default_input(id);
+
+    let device = AudioDevice::default_input().unwrap();
+    assert!(device.is_default().unwrap());
+    assert_eq!(device.avg_volume().unwrap(), 0.25);
+
+    device.set_volume(0.5).unwrap();
+    assert_eq!(device.volume_for_channel(1).unwrap(), 0.5);
+    assert!(device.set_volume(-0.5).is_err());
+
+    device.set_mute(true).unwrap();
+    assert!(device.muted().unwrap());
+
+    let output = AudioDevice::from_id(id).unwrap().as_output().unwrap();
+    assert_eq!(output.avg_volume().unwrap(), 0.25);
+    assert!(!output.muted().unwrap());
+    assert!(!output.is_default().unwrap());
+}
+
+#[test]
+fn volume_control_capabilities() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let kind = |device: SimDevice| {
+        let id = sim.add_device(device.output_channels(2));
+        let device = AudioDevice::from_id(id).unwrap().as_output().unwrap();
+        device.volume_control().unwrap().kind()
+    };
+
+    assert_eq!(kind(SimDevice::new("A")), VolumeControlKind::PerChannel);
+    assert_eq!(
+        kind(SimDevice::new("B").master_volume(true)),
+        VolumeControlKind::Both
+    );
+    assert_eq!(
+        kind(SimDevice::new("C").channel_volume(false)),
+        VolumeControlKind::None
+    );
+
+    let id = sim.add_device(
+        SimDevice::new("Master only")
+            .output_channels(2)
+            .master_volume(true)
+            .channel_volume(false)
+            .volume(0.25),
+    );
+    let device = AudioDevice::from_id(id).unwrap().as_output().unwrap();
+    let control = device.volume_control().unwrap();
+
+    assert_eq!(control.kind(), VolumeControlKind::MasterOnly);
+    assert_eq!(control.volume_elements(), [0]);
+    assert!(control.settable);
+    assert_eq!(control.mute, [0]);
+    assert_eq!(device.avg_volume().unwrap(), 0.25);
+
+    device.set_volume(0.5).unwrap();
+    assert_eq!(device.volume_for_channel(0).unwrap(), 0.5);
+    assert_eq!(device.volume_for_channel(2).unwrap(), 0.5);
+    device.set_volume_for_channel(1, 0.75).unwrap();
+    assert_eq!(device.avg_volume().unwrap(), 0.75);
+    assert!(matches!(
+        device.set_balance(0.5),
+        Err(Error::NoVolumeControl)
+    ));
+
+    sim.define_property(
+        id,
+        AudioObjPropAddress::new(
+            PropertySelector::DEV_VOLUME_SCALAR,
+            PropertyScope::DEV_OUTPUT,
+        ),
+        0.75f32.to_ne_bytes(),
+        false,
+    );
+    assert!(!device.volume_control().unwrap().settable);
+    assert!(matches!(
+        device.set_volume(0.5),
+        Err(Error::VolumeNotSettable)
+    ));
+    assert_eq!(device.avg_volume().unwrap(), 0.75);
+
+    let id = sim.add_device(
+        SimDevice::new("Both")
+            .output_channels(2)
+            .master_volume(true)
+            .volume(0.8),
+    );
+    let device = AudioDevice::from_id(id).unwrap().as_output().unwrap();
+    let master = AudioObjPropAddress::new(
+        PropertySelector::DEV_VOLUME_SCALAR,
+        PropertyScope::DEV_OUTPUT,
+    );
+
+    assert_eq!(device.volume_control().unwrap().volume_elements(), [1, 2]);
+    sim.update_property(id, master, 0.5f32.to_ne_bytes());
+    assert_eq!(device.avg_volume().unwrap(), 0.4);
+    sim.update_property(id, master, 0.0f32.to_ne_bytes());
+    assert_eq!(device.avg_volume().unwrap(), 0.0);
+
+    device.set_volume(0.6).unwrap();
+    assert_eq!(device.volume_for_channel(0).unwrap(), 1.0);
+    assert_eq!(device.volume_for_channel(1).unwrap(), 0.6);
+    assert_eq!(device.avg_volume().unwrap(), 0.6);
+
+    let silent = sim.add_device(
+        SimDevice::new("No controls")
+            .output_channels(2)
+            .channel_volume(false),
+    );
+    let silent = AudioDevice::from_id(silent).unwrap().as_output().unwrap();
+    assert!(matches!(silent.avg_volume(), Err(Error::NoVolumeControl)));
+    assert!(matches!(
+        silent.on_volume_changed(|_| {}),
+        Err(Error::NoVolumeControl)
+    ));
+    assert!(!silent.muted().unwrap());
+}
+
+#[test]
+fn balance_and_channel_volume() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Speakers").output_channels(2).volume(0.8));
+    let device = AudioDevice::from_id(id).unwrap().as_output().unwrap();
+
+    device.set_volume(1.0).unwrap();
+    assert_eq!(device.balance().unwrap(), 0.0);
+
+    device.set_balance(-0.5).unwrap();
+    assert_eq!(device.volume_for_channel(1).unwrap(), 1.0);
+    assert_eq!(device.volume_for_channel(2).unwrap(), 0.5);
+    assert_eq!(device.balance().unwrap(), -0.5);
+    assert!(device.set_balance(1.5).is_err());
+
+    device
+        .set_volume_with(0.5, VolumeMode::Proportional)
+        .unwrap();
+    assert_eq!(device.volume_for_channel(1).unwrap(), 0.5);
+    assert_eq!(device.volume_for_channel(2).unwrap(), 0.25);
+    assert_eq!(device.balance().unwrap(), -0.5);
+
+    device.set_volume_for_channel(1, 0.25).unwrap();
+    assert_eq!(device.balance().unwrap(), 0.0);
+    assert!(device.set_volume_for_channel(1, 1.01).is_err());
+
+    device.set_volume(0.4).unwrap();
+    assert_eq!(device.volume_for_channel(2).unwrap(), 0.4);
+
+    // Balance moves around the average, keeping the sum of both sides.
+    device.set_volume(0.5).unwrap();
+    let loudness = || device.volume_for_channel(1).unwrap() + device.volume_for_channel(2).unwrap();
+
+    for balance in [0.5, -0.25, 1.0, 0.0] {
+        device.set_balance(balance).unwrap();
+        assert!((loudness() - 1.0).abs() < 1e-6);
+        assert!((device.balance().unwrap() - balance).abs() < 1e-6);
+    }
+    assert_eq!(device.volume_for_channel(1).unwrap(), 0.5);
+
+    let mono = sim.add_device(SimDevice::new("Mono").output_channels(1));
+    let mono = AudioDevice::from_id(mono).unwrap().as_output().unwrap();
+    assert!(matches!(mono.balance(), Err(Error::NoStereoPair)));
+}
+
+#[test]
+fn decibel_volume() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Speakers")
+            .output_channels(2)
+            .volume(0.5)
+            .decibel_range(-64.0, 0.0),
+    );
+    let scope = PropertyScope::DEV_OUTPUT;
+    let device = AudioDevice::from_id(id).unwrap().as_output().unwrap();
+
+    assert_eq!(device.decibel_range(scope, 1).unwrap(), -64.0..=0.0);
+    assert_eq!(device.volume_db_for_channel(1).unwrap(), -32.0);
+
+    device.set_volume_db(-16.0).unwrap();
+    assert_eq!(device.volume_for_channel(1).unwrap(), 0.75);
+    assert_eq!(device.volume_for_channel(2).unwrap(), 0.75);
+    assert!(matches!(
+        device.set_volume_db(3.0),
+        Err(Error::DecibelsOutOfRange { max, .. }) if max == 0.0
+    ));
+
+    // Devices with their own curve translate values themselves.
+    sim.define_property(
+        id,
+        AudioObjPropAddress::new_with_element(
+            PropertySelector::DEV_VOLUME_SCALAR_TO_DECIBELS,
+            scope,
+            2,
+        ),
+        (-6.0f32).to_ne_bytes(),
+        false,
+    );
+    assert_eq!(device.volume_db_for_channel(2).unwrap(), -6.0);
+    assert_eq!(device.volume_db_for_channel(1).unwrap(), -16.0);
+}
+
+fn describe<S: DeviceScope>(device: &ScopedDevice<S>) -> (PropertyScope, usize, f32) {
+    (
+        device.scope(),
+        device.channels().len(),
+        device.avg_volume().unwrap(),
+    )
+}
+
+#[test]
+fn scoped_devices_share_controls() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Interface")
+            .input_channels(1)
+            .output_channels(2)
+            .volume(0.5),
+    );
+    let play_through = |selector, element| {
+        AudioObjPropAddress::new_with_element(selector, PropertyScope::DEV_PLAY_THROUGH, element)
+    };
+    sim.define_property(
+        id,
+        play_through(PropertySelector::DEV_VOLUME_SCALAR, 1),
+        0.25f32.to_ne_bytes(),
+        true,
+    );
+    sim.define_property(
+        id,
+        play_through(PropertySelector::DEV_MUTE, 0),
+        1u32.to_ne_bytes(),
+        true,
+    );
+    sim.define_property(
+        id,
+        AudioObjPropAddress::new(PropertySelector::DEV_DATA_SOURCE, PropertyScope::DEV_OUTPUT),
+        u32::from_be_bytes(*b"ispk").to_ne_bytes(),
+        true,
+    );
+
+    let input = AudioDevice::from_id(id).unwrap().as_input().unwrap();
+    let output = AudioDevice::from_id(id).unwrap().as_output().unwrap();
+    let monitor = AudioDevice::from_id(id).unwrap().as_play_through().unwrap();
+
+    assert_eq!(describe(&input), (PropertyScope::DEV_INPUT, 1, 0.5));
+    assert_eq!(describe(&output), (PropertyScope::DEV_OUTPUT, 2, 0.5));
+    assert_eq!(
+        describe(&monitor),
+        (PropertyScope::DEV_PLAY_THROUGH, 1, 0.25)
+    );
+
+    assert!(monitor.muted().unwrap());
+    monitor.set_mute(false).unwrap();
+    assert!(!monitor.muted().unwrap());
+    assert!(!output.muted().unwrap());
+
+    output
+        .set_data_source(u32::from_be_bytes(*b"hdpn"))
+        .unwrap();
+    assert_eq!(output.data_source().unwrap(), u32::from_be_bytes(*b"hdpn"));
+
+    assert!(output.has_property(play_through(PropertySelector::DEV_MUTE, 0)));
+    assert!(monitor.downgrade().as_play_through().is_some());
+}
+
+#[test]
+fn default_device_without_channels_in_scope() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let mic = sim.add_device(SimDevice::new("Microphone").input_channels(1));
+    let speakers = sim.add_device(SimDevice::new("Speakers").output_channels(2));
+    sim.set_default_output(mic);
+    sim.set_default_input(speakers);
+
+    assert!(matches!(
+        AudioDevice::default_output(),
+        Err(Error::NotOutput)
+    ));
+    assert!(matches!(AudioDevice::default_input(), Err(Error::NotInput)));
+}
+
+#[test]
+fn default_output_changed_event() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let first = sim.add_device(SimDevice::new("Built-in").output_channels(2));
+    let second = sim.add_device(SimDevice::new("USB DAC").output_channels(2));
+    sim.set_default_output(first);
+
+    let seen_a = Arc::new(AtomicU32::new(0));
+    let seen_b = Arc::new(AtomicU32::new(0));
+
+    let sink = seen_a.clone();
+    let subscription_a = output_changed::register(Box::new(move |device: AudioOutputDevice| {
+        sink.store(device.id(), Ordering::SeqCst);
+    }))
+    .unwrap();
+    let sink = seen_b.clone();
+    let subscription_b = output_changed::register(Box::new(move |device: AudioOutputDevice| {
+        sink.store(device.id(), Ordering::SeqCst);
+    }))
+    .unwrap();
+
+    sim.set_default_output(second);
+    assert_eq!(seen_a.load(Ordering::SeqCst), second);
+    assert_eq!(seen_b.load(Ordering::SeqCst), second);
+
+    output_changed::unregister(subscription_a).unwrap();
+    sim.set_default_output(first);
+    assert_eq!(seen_a.load(Ordering::SeqCst), second);
+    assert_eq!(seen_b.load(Ordering::SeqCst), first);
+
+    drop(subscription_b);
+    sim.set_default_output(second);
+    assert_eq!(seen_b.load(Ordering::SeqCst), first);
+}
+
+#[test]
+fn property_listener_reports_changed_addresses() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Speakers").output_channels(2));
+    let address = AudioObjPropAddress::new_with_element(
+        PropertySelector::DEV_VOLUME_SCALAR,
+        PropertyScope::DEV_OUTPUT,
+        2,
+    );
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let sink = seen.clone();
+    let listener = PropertyListener::new(id, address, move |object, changed| {
+        sink.lock().unwrap().push((object, changed.to_vec()));
+    })
+    .unwrap();
+
+    sim.update_property(id, address, 0.1f32.to_ne_bytes());
+    assert_eq!(*seen.lock().unwrap(), [(id, vec![address])]);
+
+    drop(listener);
+    sim.update_property(id, address, 0.2f32.to_ne_bytes());
+    assert_eq!(seen.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn unregister_while_callback_is_running() {
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Speakers").output_channels(2));
+    let address =
+        AudioObjPropAddress::new(PropertySelector::DEV_OBJECT_NAME, PropertyScope::OBJ_GLOBAL);
+
+    let (entered_tx, entered) = mpsc::channel();
+    let (resume, resume_rx) = mpsc::channel::<()>();
+    let (entered_tx, resume_rx) = (Mutex::new(entered_tx), Mutex::new(resume_rx));
+    let dropped = Arc::new(AtomicBool::new(false));
+    let flag = DropFlag(dropped.clone());
+    let calls = Arc::new(AtomicU32::new(0));
+
+    let counter = calls.clone();
+    let listener = PropertyListener::new(id, address, move |_, _| {
+        entered_tx.lock().unwrap().send(()).unwrap();
+        resume_rx.lock().unwrap().recv().unwrap();
+        // Still readable after unregistering, since this call holds the handlers.
+        assert!(!flag.0.load(Ordering::SeqCst));
+        counter.fetch_add(1, Ordering::SeqCst);
+    })
+    .unwrap();
+
+    let notifier = {
+        let sim = sim.clone();
+        thread::spawn(move || sim.update_property(id, address, "Monitors".as_bytes()))
+    };
+
+    entered.recv().unwrap();
+    listener.unregister().unwrap();
+    assert!(!dropped.load(Ordering::SeqCst));
+
+    resume.send(()).unwrap();
+    notifier.join().unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(dropped.load(Ordering::SeqCst));
+
+    sim.update_property(id, address, "Speakers".as_bytes());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn volume_and_mute_events() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Speakers").output_channels(2).volume(0.5));
+    sim.set_default_output(id);
+    let device = AudioDevice::default_output().unwrap();
+
+    let volumes = Arc::new(Mutex::new(Vec::new()));
+    let sink = volumes.clone();
+    let _volume = device
+        .on_volume_changed(move |event| sink.lock().unwrap().push(event))
+        .unwrap();
+
+    let mutes = Arc::new(Mutex::new(Vec::new()));
+    let sink = mutes.clone();
+    let _mute = device
+        .on_mute_changed(move |event| sink.lock().unwrap().push(event.muted))
+        .unwrap();
+
+    let channel = |ch| {
+        AudioObjPropAddress::new_with_element(
+            PropertySelector::DEV_VOLUME_SCALAR,
+            PropertyScope::DEV_OUTPUT,
+            ch,
+        )
+    };
+    sim.update_properties(
+        id,
+        [
+            (channel(1), 0.25f32.to_ne_bytes().to_vec()),
+            (channel(2), 0.75f32.to_ne_bytes().to_vec()),
+        ],
+    );
+
+    let volumes = volumes.lock().unwrap();
+    assert_eq!(volumes.len(), 1);
+    assert_eq!(volumes[0].channels, [(1, 0.25), (2, 0.75)]);
+    assert_eq!(volumes[0].average, 0.5);
+    assert_eq!(volumes[0].master, None);
+
+    device.set_mute(true).unwrap();
+    device.set_mute(true).unwrap();
+    device.set_mute(false).unwrap();
+    assert_eq!(*mutes.lock().unwrap(), [true, false]);
+}
+
+#[test]
+fn master_volume_events() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Speakers")
+            .output_channels(2)
+            .master_volume(true)
+            .volume(0.5),
+    );
+    let device = AudioDevice::from_id(id).unwrap().as_output().unwrap();
+
+    let volumes = Arc::new(Mutex::new(Vec::new()));
+    let sink = volumes.clone();
+    let _volume = device
+        .on_volume_changed(move |event| sink.lock().unwrap().push(event))
+        .unwrap();
+
+    sim.update_property(
+        id,
+        AudioObjPropAddress::new(
+            PropertySelector::DEV_VOLUME_SCALAR,
+            PropertyScope::DEV_OUTPUT,
+        ),
+        0.25f32.to_ne_bytes(),
+    );
+
+    let volumes = volumes.lock().unwrap();
+    assert_eq!(volumes.len(), 1);
+    assert_eq!(volumes[0].master, Some(0.25));
+    assert_eq!(volumes[0].channels, [(1, 0.5), (2, 0.5)]);
+}
+
+#[test]
+fn device_list_events() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let built_in = sim.add_device(SimDevice::new("Built-in").output_channels(2));
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    let _subscription = devices_changed::register(Box::new(move |event| {
+        let summary = match event {
+            DeviceListEvent::DeviceAdded(device) => {
+                (true, device.id(), Some(device.name().to_owned()))
+            }
+            DeviceListEvent::DeviceRemoved(id, name) => (false, id, name),
+        };
+        sink.lock().unwrap().push(summary);
+    }))
+    .unwrap();
+
+    let headset = sim.add_device(
+        SimDevice::new("Headset")
+            .input_channels(1)
+            .output_channels(2),
+    );
+    sim.remove_device(built_in);
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            (true, headset, Some("Headset".to_owned())),
+            (false, built_in, Some("Built-in".to_owned())),
+        ]
+    );
+}
+
+#[test]
+fn removed_devices_carry_their_last_name() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Speakers").output_channels(2));
+
+    let removed = Arc::new(Mutex::new(Vec::new()));
+    let sink = removed.clone();
+    let _subscription = devices_changed::register(Box::new(move |event| {
+        if let DeviceListEvent::DeviceRemoved(id, name) = event {
+            sink.lock().unwrap().push((id, name));
+        }
+    }))
+    .unwrap();
+
+    sim.update_property(
+        id,
+        AudioObjPropAddress::new(PropertySelector::DEV_OBJECT_NAME, PropertyScope::OBJ_GLOBAL),
+        "Studio Monitors".as_bytes(),
+    );
+    sim.remove_device(id);
+
+    assert_eq!(
+        *removed.lock().unwrap(),
+        [(id, Some("Studio Monitors".to_owned()))]
+    );
+}
+
+#[test]
+fn device_list_errors_reach_error_handler() {
+    // kAudioObjectSystemObject
+    const SYSTEM_OBJECT: u32 = 1;
+    let devices =
+        AudioObjPropAddress::new(PropertySelector::HW_ALL_DEVICES, PropertyScope::OBJ_GLOBAL);
+
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let unnamed = sim.add_device(SimDevice::new("Speakers").output_channels(2));
+    sim.define_property(
+        unnamed,
+        AudioObjPropAddress::new(PropertySelector::DEV_OBJECT_NAME, PropertyScope::OBJ_GLOBAL),
+        [0xff],
+        true,
+    );
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let errors = Arc::new(AtomicU32::new(0));
+    let (sink, error_count) = (events.clone(), errors.clone());
+    let _subscription = devices_changed::register_with_error_handler(
+        Box::new(move |event| {
+            let summary = match event {
+                DeviceListEvent::DeviceAdded(device) => {
+                    (true, device.id(), Some(device.name().to_owned()))
+                }
+                DeviceListEvent::DeviceRemoved(id, name) => (false, id, name),
+            };
+            sink.lock().unwrap().push(summary);
+        }),
+        Box::new(move |_| {
+            error_count.fetch_add(1, Ordering::SeqCst);
+        }),
+    )
+    .unwrap();
+    assert_eq!(errors.load(Ordering::SeqCst), 1);
+
+    let headset = sim.add_device(SimDevice::new("Headset").output_channels(2));
+
+    // An object that isn't a readable device shows up in the list.
+    let mut list = sim.property_data(SYSTEM_OBJECT, devices).unwrap();
+    list.extend_from_slice(&900u32.to_ne_bytes());
+    sim.update_property(SYSTEM_OBJECT, devices, list);
+    assert_eq!(errors.load(Ordering::SeqCst), 2);
+
+    sim.remove_device(unnamed);
+
+    assert_eq!(errors.load(Ordering::SeqCst), 3);
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            (true, headset, Some("Headset".to_owned())),
+            (false, unnamed, None),
+        ]
+    );
+}
+
+#[test]
+fn event_errors_and_panics_reach_error_handler() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Speakers").output_channels(2));
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let sink = errors.clone();
+    let _subscription = output_changed::register_with_error_handler(
+        Box::new(|_| panic!("subscriber bug")),
+        Box::new(move |err| sink.lock().unwrap().push(err.to_string())),
+    )
+    .unwrap();
+
+    sim.set_default_output(id);
+    sim.set_default_output(9999);
+
+    let errors = errors.lock().unwrap();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0], "Event callback panicked: subscriber bug");
+    assert_eq!(
+        errors[1],
+        "bad object ('!obj') for property ObjectName (Global, element 0) on object 9999"
+    );
+}
+
+#[test]
+fn typed_properties() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Speakers").output_channels(2).volume(0.25));
+    sim.set_default_output(id);
+    let device = AudioDevice::default_output().unwrap();
+
+    assert_eq!(
+        device.get_element(property::VOLUME_SCALAR, 2).unwrap(),
+        0.25
+    );
+    device.set(property::MUTE, &true).unwrap();
+    assert!(device.get(property::MUTE).unwrap());
+    assert_eq!(device.get(property::STREAMS).unwrap().len(), 1);
+
+    let label = AudioObjPropAddress::new(PropertySelector::DEV_NAME, PropertyScope::DEV_OUTPUT);
+    sim.define_property(id, label, "Desk", true);
+    device
+        .set_property(label, &"Desk speakers".to_owned())
+        .unwrap();
+    assert_eq!(
+        device.get_property::<String>(label).unwrap(),
+        "Desk speakers"
+    );
+}
+
+#[test]
+fn array_properties() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Built-in Output").output_channels(2));
+    let device = AudioDevice::from_id(id).unwrap();
+
+    let sources = AudioObjPropAddress::new(
+        PropertySelector::DEV_DATA_SOURCES,
+        PropertyScope::DEV_OUTPUT,
+    );
+    let sources_data: Vec<u8> = [u32::from_be_bytes(*b"ispk"), u32::from_be_bytes(*b"hdpn")]
+        .iter()
+        .flat_map(|source| source.to_ne_bytes())
+        .collect();
+    sim.define_property(id, sources, sources_data, false);
+
+    assert_eq!(
+        device.data_sources(PropertyScope::DEV_OUTPUT).unwrap(),
+        [u32::from_be_bytes(*b"ispk"), u32::from_be_bytes(*b"hdpn")]
+    );
+    assert!(device.data_sources(PropertyScope::DEV_INPUT).is_err());
+}
+
+#[test]
+fn array_reads_retry_when_the_list_changes() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Built-in Output").output_channels(2));
+    let device = AudioDevice::from_id(id).unwrap();
+    let scope = PropertyScope::DEV_OUTPUT;
+    let sources = AudioObjPropAddress::new(PropertySelector::DEV_DATA_SOURCES, scope);
+    let list =
+        |items: &[u32]| -> Vec<u8> { items.iter().flat_map(|item| item.to_ne_bytes()).collect() };
+    sim.define_property(id, sources, list(&[1, 2]), false);
+
+    // The list grows between querying its size and reading it.
+    sim.script_read(id, sources, ReadFault::Change(list(&[1, 2, 3])));
+    assert_eq!(device.data_sources(scope).unwrap(), [1, 2, 3]);
+
+    sim.script_read(id, sources, ReadFault::BadPropertySize);
+    sim.script_read(id, sources, ReadFault::BadPropertySize);
+    assert_eq!(device.data_sources(scope).unwrap(), [1, 2, 3]);
+
+    // Out of attempts: the size error is returned...
+    for _ in 0..3 {
+        sim.script_read(id, sources, ReadFault::BadPropertySize);
+    }
+    assert!(matches!(
+        device.data_sources(scope),
+        Err(Error::Hal(err)) if err.kind() == HalErrorKind::BadPropertySize
+    ));
+
+    // ...and so is a list that keeps growing, instead of a truncated one.
+    for len in 4..=6 {
+        let items: Vec<u32> = (1..=len).collect();
+        sim.script_read(id, sources, ReadFault::Change(list(&items)));
+    }
+    assert!(matches!(
+        device.data_sources(scope),
+        Err(Error::Hal(err)) if err.kind() == HalErrorKind::BadPropertySize
+    ));
+}
+
+#[test]
+fn device_strings() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Scarlett 2i2 USB")
+            .uid("AppleUSBAudioEngine:Focusrite:Scarlett 2i2 USB:1")
+            .model_uid("Scarlett 2i2 USB:1235:8210")
+            .manufacturer("Focusrite")
+            .output_channels(2),
+    );
+
+    let mut device = AudioDevice::from_id(id).unwrap();
+    assert_eq!(
+        device.uid().unwrap(),
+        "AppleUSBAudioEngine:Focusrite:Scarlett 2i2 USB:1"
+    );
+    assert_eq!(device.model_uid().unwrap(), "Scarlett 2i2 USB:1235:8210");
+    assert_eq!(device.manufacturer().unwrap(), "Focusrite");
+
+    let long_name = "Studio monitors (left desk, behind the rack) — 🎧".repeat(8);
+    device.set_name(&long_name).unwrap();
+    assert_eq!(device.name(), long_name);
+    assert_eq!(AudioDevice::from_id(id).unwrap().name(), long_name);
+}
+
+#[test]
+fn device_identity_by_uid() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let uid = "AppleUSBAudioEngine:Focusrite:Scarlett 2i2 USB:1";
+    let focusrite = SimDevice::new("Scarlett 2i2 USB")
+        .uid(uid)
+        .output_channels(2);
+
+    let first_id = sim.add_device(focusrite.clone());
+    sim.add_device(SimDevice::new("Speakers").output_channels(2));
+    let before = AudioDevice::from_uid(uid).unwrap();
+    assert_eq!(before.id(), first_id);
+
+    sim.remove_device(first_id);
+    assert!(devices::find_by_uid(uid).unwrap().is_none());
+    assert!(matches!(
+        AudioDevice::from_uid(uid),
+        Err(Error::UnknownDeviceUid(missing)) if missing == uid
+    ));
+
+    let second_id = sim.add_device(focusrite);
+    let after = devices::find_by_uid(uid).unwrap().unwrap();
+    assert_ne!(after.id(), before.id());
+    assert_eq!(after.id(), second_id);
+    assert_eq!(after, before);
+
+    let unique: HashSet<_> = get_all_devices().unwrap().into_iter().collect();
+    assert_eq!(unique.len(), 2);
+    assert!(unique.contains(&before));
+}
+
+#[test]
+fn nominal_sample_rate() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Interface")
+            .output_channels(2)
+            .nominal_sample_rate(44_100.0)
+            .sample_rates(&[44_100.0, 48_000.0]),
+    );
+    let device = AudioDevice::from_id(id).unwrap();
+
+    let ranges = device.available_nominal_sample_rates().unwrap();
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(
+        (ranges[1].mMinimum, ranges[1].mMaximum),
+        (48_000.0, 48_000.0)
+    );
+    assert_eq!(device.nominal_sample_rate().unwrap(), 44_100.0);
+
+    device.set_nominal_sample_rate(48_000.0).unwrap();
+    assert_eq!(device.nominal_sample_rate().unwrap(), 48_000.0);
+    assert!(matches!(
+        device.set_nominal_sample_rate(96_000.0),
+        Err(Error::UnsupportedSampleRate(rate)) if rate == 96_000.0
+    ));
+
+    device
+        .set_nominal_sample_rate_blocking(44_100.0, Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(device.nominal_sample_rate().unwrap(), 44_100.0);
+
+    // Hardware that refuses the switch and stays at 44.1 kHz.
+    let rate = AudioObjPropAddress::new(
+        PropertySelector::DEV_NOMINAL_SAMPLE_RATE,
+        PropertyScope::OBJ_GLOBAL,
+    );
+    let hardware = sim.clone();
+    let _revert = PropertyListener::new(id, rate, move |_, _| {
+        if hardware.property_data(id, rate).unwrap() != 44_100f64.to_ne_bytes() {
+            hardware.update_property(id, rate, 44_100f64.to_ne_bytes());
+        }
+    })
+    .unwrap();
+
+    assert!(matches!(
+        device.set_nominal_sample_rate_blocking(48_000.0, Duration::from_millis(50)),
+        Err(Error::SampleRateTimeout(rate)) if rate == 48_000.0
+    ));
+}
+
+#[test]
+fn buffer_frame_size() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Interface")
+            .output_channels(2)
+            .buffer_frame_size(256)
+            .buffer_frame_size_range(32, 2048),
+    );
+    let device = AudioDevice::from_id(id).unwrap();
+
+    assert_eq!(device.buffer_frame_size().unwrap(), 256);
+    assert_eq!(device.buffer_frame_size_range().unwrap(), 32..=2048);
+
+    device.set_buffer_frame_size(64).unwrap();
+    assert_eq!(device.buffer_frame_size().unwrap(), 64);
+
+    assert!(matches!(
+        device.set_buffer_frame_size(16),
+        Err(Error::BufferFrameSizeOutOfRange {
+            size: 16,
+            min: 32,
+            max: 2048
+        })
+    ));
+    assert_eq!(device.buffer_frame_size().unwrap(), 64);
+}
+
+#[test]
+fn latency_report() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Interface")
+            .input_channels(2)
+            .output_channels(2)
+            .nominal_sample_rate(48_000.0)
+            .buffer_frame_size(96)
+            .latency(24)
+            .safety_offset(48)
+            .stream_latency(72),
+    );
+    let device = AudioDevice::from_id(id).unwrap();
+
+    for scope in [PropertyScope::DEV_INPUT, PropertyScope::DEV_OUTPUT] {
+        let report = device.latency_report(scope).unwrap();
+
+        assert_eq!(report.scope, scope);
+        assert_eq!(report.device, Latency::from_frames(24, 48_000.0));
+        assert_eq!(report.safety_offset.frames, 48);
+        assert_eq!(report.safety_offset.millis, 1.0);
+        assert_eq!(report.buffer.millis, 2.0);
+        assert_eq!(report.streams, [Latency::from_frames(72, 48_000.0)]);
+        assert_eq!(report.total().frames, 240);
+        assert_eq!(report.total().millis, 5.0);
+    }
+
+    assert!(device.latency_report(PropertyScope::OBJ_GLOBAL).is_err());
+
+    let mut report = device.latency_report(PropertyScope::DEV_OUTPUT).unwrap();
+    report.device = Latency::from_frames(u32::MAX - 10, 48_000.0);
+    assert_eq!(report.total().frames, u32::MAX);
+}
+
+#[test]
+fn transport_type_and_flags() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("AirPods")
+            .output_channels(2)
+            .transport_type(TransportType::Bluetooth),
+    );
+    let hidden = sim.add_device(
+        SimDevice::new("Sub-device")
+            .output_channels(2)
+            .transport_type(TransportType::Other(u32::from_be_bytes(*b"ccwl")))
+            .hidden(true),
+    );
+
+    let device = AudioDevice::from_id(id).unwrap();
+    assert_eq!(device.transport_type().unwrap(), TransportType::Bluetooth);
+    assert!(device.transport_type().unwrap().is_wireless());
+    assert!(!device.is_hidden().unwrap());
+    assert!(device.is_alive().unwrap());
+    assert!(!device.is_running().unwrap());
+    assert!(device.can_be_default(PropertyScope::DEV_OUTPUT).unwrap());
+
+    let running =
+        AudioObjPropAddress::new(PropertySelector::DEV_IS_RUNNING, PropertyScope::OBJ_GLOBAL);
+    sim.update_property(id, running, 1u32.to_ne_bytes());
+    assert!(device.is_running().unwrap());
+
+    let hidden = AudioDevice::from_id(hidden).unwrap();
+    assert!(hidden.is_hidden().unwrap());
+    assert_eq!(
+        hidden.transport_type().unwrap(),
+        TransportType::from_raw(u32::from_be_bytes(*b"ccwl"))
+    );
+    assert_eq!(
+        TransportType::from_raw(u32::from_be_bytes(*b"usb ")),
+        TransportType::Usb
+    );
+}
+
+#[test]
+fn device_streams() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Headset")
+            .input_channels(1)
+            .output_channels(2)
+            .nominal_sample_rate(44_100.0)
+            .stream_latency(32),
+    );
+    let device = AudioDevice::from_id(id).unwrap();
+
+    let outputs = device.streams(PropertyScope::DEV_OUTPUT).unwrap();
+    assert_eq!(outputs.len(), 1);
+    let output = outputs[0];
+    assert_eq!(output.direction().unwrap(), StreamDirection::Output);
+    assert_eq!(output.starting_channel().unwrap(), 1);
+    assert_eq!(output.terminal_type().unwrap(), TerminalType::Speaker);
+    assert!(output.is_active().unwrap());
+    assert_eq!(output.latency().unwrap(), 32);
+
+    let format = output.virtual_format().unwrap();
+    assert_eq!(format.sample_rate, 44_100.0);
+    assert_eq!(format.channels_per_frame, 2);
+    assert_eq!(format.bits_per_channel, 32);
+    assert_eq!(output.physical_format().unwrap().bits_per_channel, 24);
+
+    let inputs = device.streams(PropertyScope::DEV_INPUT).unwrap();
+    assert_eq!(inputs.len(), 1);
+    assert_ne!(inputs[0], output);
+    assert_eq!(inputs[0].direction().unwrap(), StreamDirection::Input);
+    assert_eq!(inputs[0].terminal_type().unwrap(), TerminalType::Microphone);
+    assert_eq!(
+        AudioStream::from_id(inputs[0].id())
+            .virtual_format()
+            .unwrap()
+            .channels_per_frame,
+        1
+    );
+}
+
+#[test]
+fn streams_are_ordered_by_starting_channel() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(SimDevice::new("Interface").output_channels(4));
+    let global = |selector| AudioObjPropAddress::new(selector, PropertyScope::OBJ_GLOBAL);
+
+    for (stream, channel) in [(900u32, 3u32), (901, 1)] {
+        sim.define_property(
+            stream,
+            global(PropertySelector::STM_STARTING_CHANNEL),
+            channel.to_ne_bytes(),
+            false,
+        );
+    }
+    sim.define_property(
+        id,
+        AudioObjPropAddress::new(PropertySelector::DEV_STREAMS, PropertyScope::DEV_OUTPUT),
+        [900u32, 901]
+            .iter()
+            .flat_map(|stream| stream.to_ne_bytes())
+            .collect::<Vec<u8>>(),
+        false,
+    );
+
+    let device = AudioDevice::from_id(id).unwrap();
+    let streams = device.streams(PropertyScope::DEV_OUTPUT).unwrap();
+    assert_eq!(
+        streams,
+        [AudioStream::from_id(901), AudioStream::from_id(900)]
+    );
+
+    sim.define_property(
+        id,
+        AudioObjPropAddress::new(PropertySelector::DEV_STREAMS, PropertyScope::DEV_OUTPUT),
+        902u32.to_ne_bytes(),
+        false,
+    );
+    assert!(device.streams(PropertyScope::DEV_OUTPUT).is_err());
+}
+
+#[test]
+fn switch_physical_format() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("DAC")
+            .output_channels(2)
+            .sample_rates(&[44_100.0, 96_000.0]),
+    );
+    let device = AudioDevice::from_id(id).unwrap();
+    let stream = device.streams(PropertyScope::DEV_OUTPUT).unwrap()[0];
+
+    let physical = stream.available_physical_formats().unwrap();
+    assert_eq!(physical.len(), 4);
+    assert!(physical
+        .iter()
+        .all(|ranged| ranged.format.format_id == FormatId::LinearPcm && !ranged.format.is_float()));
+
+    let target = physical
+        .iter()
+        .find_map(|ranged| {
+            ranged
+                .at_sample_rate(96_000.0)
+                .filter(|format| format.bits_per_channel == 24)
+        })
+        .unwrap();
+    stream.set_physical_format(&target).unwrap();
+    assert_eq!(stream.physical_format().unwrap(), target);
+
+    let virtual_formats = stream.available_virtual_formats().unwrap();
+    assert!(virtual_formats
+        .iter()
+        .all(|ranged| ranged.format.flags.contains(FormatFlag::Float)));
+    let float = StreamFormat::linear_pcm(96_000.0, 2, 32, true, true);
+    stream.set_virtual_format(&float).unwrap();
+    assert_eq!(stream.virtual_format().unwrap(), float);
+}
+
+#[test]
+fn stereo_pair_and_channel_layout() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let id = sim.add_device(
+        SimDevice::new("Surround")
+            .output_channels(6)
+            .output_layout(&[
+                ChannelLabel::Left,
+                ChannelLabel::Right,
+                ChannelLabel::Center,
+                ChannelLabel::LfeScreen,
+                ChannelLabel::LeftSurround,
+                ChannelLabel::RightSurround,
+            ]),
+    );
+    let device = AudioDevice::from_id(id).unwrap();
+    let scope = PropertyScope::DEV_OUTPUT;
+
+    assert_eq!(device.preferred_stereo_channels(scope).unwrap(), [1, 2]);
+    device.set_preferred_stereo_channels(scope, [5, 6]).unwrap();
+    assert_eq!(device.preferred_stereo_channels(scope).unwrap(), [5, 6]);
+    assert!(matches!(
+        device.set_preferred_stereo_channels(scope, [5, 7]),
+        Err(Error::InvalidChannel(7))
+    ));
+
+    let layout = device.preferred_channel_layout(scope).unwrap();
+    assert_eq!(layout.channels.len(), 6);
+    assert_eq!(layout.label(4), Some(ChannelLabel::LfeScreen));
+    assert_eq!(layout.element(ChannelLabel::Center), Some(3));
+}
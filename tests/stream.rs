@@ -0,0 +1,41 @@
+#![cfg(feature = "futures")]
+
+use coreaudio_hl::{
+    error::Error,
+    events::stream,
+    hal::{self, SimDevice, SimulatedHal},
+};
+use futures_core::Stream;
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+
+fn poll<S: Stream + Unpin>(stream: &mut S) -> Poll<Option<S::Item>> {
+    let mut cx = Context::from_waker(Waker::noop());
+    Pin::new(stream).poll_next(&mut cx)
+}
+
+#[test]
+fn default_output_stream_reports_lag() {
+    let sim = Arc::new(SimulatedHal::new());
+    let _backend = hal::scoped_backend(sim.clone());
+    let first = sim.add_device(SimDevice::new("Built-in").output_channels(2));
+    let second = sim.add_device(SimDevice::new("USB DAC").output_channels(2));
+    let mut changes = stream::default_output_changes(2).unwrap();
+
+    assert!(poll(&mut changes).is_pending());
+
+    sim.set_default_output(first);
+    sim.set_default_output(second);
+    sim.set_default_output(first);
+
+    assert!(matches!(
+        poll(&mut changes),
+        Poll::Ready(Some(Err(Error::Lagged(1))))
+    ));
+    assert!(matches!(poll(&mut changes), Poll::Ready(Some(Ok(dev))) if dev.id() == second));
+    assert!(matches!(poll(&mut changes), Poll::Ready(Some(Ok(dev))) if dev.id() == first));
+    assert!(poll(&mut changes).is_pending());
+}
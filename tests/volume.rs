@@ -0,0 +1,26 @@
+use coreaudio_hl::devices::{decibels_to_scalar, scalar_to_decibels};
+
+#[test]
+fn scalar_maps_linearly_onto_decibel_range() {
+    let range = -64.0..=0.0;
+
+    assert_eq!(scalar_to_decibels(0.0, &range), -64.0);
+    assert_eq!(scalar_to_decibels(0.5, &range), -32.0);
+    assert_eq!(scalar_to_decibels(1.0, &range), 0.0);
+    assert_eq!(scalar_to_decibels(1.5, &range), 0.0);
+    assert_eq!(scalar_to_decibels(-1.0, &range), -64.0);
+}
+
+#[test]
+fn decibels_round_trip_to_scalar() {
+    let range = -96.0..=6.0;
+
+    for scalar in [0.0, 0.25, 0.5, 0.75, 1.0] {
+        let db = scalar_to_decibels(scalar, &range);
+        assert!((decibels_to_scalar(db, &range) - scalar).abs() < 1e-6);
+    }
+
+    assert_eq!(decibels_to_scalar(12.0, &range), 1.0);
+    assert_eq!(decibels_to_scalar(-120.0, &range), 0.0);
+    assert_eq!(decibels_to_scalar(-10.0, &(0.0..=0.0)), 1.0);
+}